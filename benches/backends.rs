@@ -0,0 +1,29 @@
+use calculator::ast::AstNode;
+use calculator::interpreter::Interpreter;
+use calculator::optimize::fold_constants;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+// Shared with the tests in tests/backends.rs, which check that every
+// backend measured here prints the same thing for it.
+const LOOPS: &str = include_str!("../tests/fixtures/loops.pas");
+
+fn run(tree: &AstNode) -> String {
+    let mut interpreter = Interpreter::default();
+    interpreter.visit(tree).unwrap();
+    interpreter.take_output()
+}
+
+// The tree-walking interpreter on the tree as parsed and on the tree after
+// constant folding. There is no bytecode VM yet; it gets a column here when
+// there is one.
+fn compare_backends(c: &mut Criterion) {
+    let tree = calculator::parse(LOOPS).unwrap();
+    let folded = fold_constants(tree.clone());
+    let mut group = c.benchmark_group("loops.pas");
+    group.bench_function("interpreter", |b| b.iter(|| run(black_box(&tree))));
+    group.bench_function("folded AST", |b| b.iter(|| run(black_box(&folded))));
+    group.finish();
+}
+
+criterion_group!(benches, compare_backends);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+use calculator::lexer::Lexer;
+use calculator::parser::Parser;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+// 1,250 groups like `(7 * 2 + 8 DIV 3)`, one for each `i` from 0, joined
+// by `+`, which comes to 9,999 nodes. The groups make the parser descend
+// through every grammar level, and the long run of `+` between them makes
+// it loop within one.
+fn expression() -> String {
+    let groups: Vec<String> = (0..1250)
+        .map(|i| format!("({} * 2 + {} DIV 3)", i, i + 1))
+        .collect();
+    groups.join(" + ")
+}
+
+fn parse_expression(c: &mut Criterion) {
+    let mut lexer = Lexer::new(expression());
+    let tokens: Vec<_> = lexer.tokens().collect::<Result<_, _>>().unwrap();
+    // Lexing happens once up front, so only the parser is measured
+    c.bench_function("parse 10,000-node expression", |b| {
+        b.iter_batched(
+            || tokens.clone(),
+            |tokens| Parser::from_tokens(black_box(tokens)).parse().unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, parse_expression);
+criterion_main!(benches);
@@ -1,12 +1,21 @@
-use crate::token::Token;
+use std::{fmt, mem};
 
+use crate::interpreter::BigInt;
+use crate::token::{Token, TokenKind};
+
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
 #[derive(Debug, Clone)]
 pub enum AstType {
     Integer(i32),
+    BigInt(BigInt),
     Real(f64),
+    Boolean(bool),
+    Str(String),
+    Char(char),
 }
 
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+#[derive(Debug, Clone, Default)]
 pub enum AstNode {
     BinaryOp(Box<AstNode>, Box<AstNode>, Token),
     Num(AstType),
@@ -14,9 +23,221 @@ pub enum AstNode {
     Var(Token),
     Assign(Box<AstNode>, Box<AstNode>, Token),
     Compound(Vec<AstNode>),
+    #[default]
     NoOp,
     Program(String, Box<AstNode>),
     Block(Vec<AstNode>, Box<AstNode>),
     VarDecl(Box<AstNode>, Box<AstNode>),
+    // A variable, the bounds of its index range and the element type
+    ArrayDecl(Box<AstNode>, i32, i32, Box<AstNode>),
+    // An array variable and the index expression
+    Index(Token, Box<AstNode>),
+    ConstDecl(String, Box<AstNode>),
     Type(Token),
+    FunctionCall(String, Vec<AstNode>),
+    // An argument to write, writeln or format, its field width and its
+    // number of decimals, or NoOp for none
+    Formatted(Box<AstNode>, Box<AstNode>, Box<AstNode>),
+    If(Box<AstNode>, Box<AstNode>, Box<AstNode>),
+    IfExpr(Box<AstNode>, Box<AstNode>, Box<AstNode>),
+    While(Box<AstNode>, Box<AstNode>),
+    Repeat(Vec<AstNode>, Box<AstNode>),
+    For(
+        Box<AstNode>,
+        Box<AstNode>,
+        Box<AstNode>,
+        TokenKind,
+        Box<AstNode>,
+    ),
+    ProcedureDecl(String, Vec<AstNode>, Box<AstNode>),
+    ProcedureCall(String, Vec<AstNode>),
+}
+
+impl AstNode {
+    // Moves the children of this node onto `nodes`, leaving it a leaf.
+    fn take_children(&mut self, nodes: &mut Vec<AstNode>) {
+        match self {
+            AstNode::BinaryOp(left, right, _)
+            | AstNode::Assign(left, right, _)
+            | AstNode::VarDecl(left, right)
+            | AstNode::While(left, right) => {
+                nodes.extend([mem::take(&mut **left), mem::take(&mut **right)])
+            }
+            AstNode::UnaryOp(child, _)
+            | AstNode::Index(_, child)
+            | AstNode::ConstDecl(_, child)
+            | AstNode::Program(_, child) => nodes.push(mem::take(&mut **child)),
+            AstNode::ArrayDecl(var, _, _, type_node) => {
+                nodes.extend([mem::take(&mut **var), mem::take(&mut **type_node)])
+            }
+            AstNode::Compound(children)
+            | AstNode::FunctionCall(_, children)
+            | AstNode::ProcedureCall(_, children) => nodes.append(children),
+            AstNode::Block(children, child)
+            | AstNode::Repeat(children, child)
+            | AstNode::ProcedureDecl(_, children, child) => {
+                nodes.append(children);
+                nodes.push(mem::take(&mut **child));
+            }
+            AstNode::Formatted(first, second, third)
+            | AstNode::If(first, second, third)
+            | AstNode::IfExpr(first, second, third) => nodes.extend([
+                mem::take(&mut **first),
+                mem::take(&mut **second),
+                mem::take(&mut **third),
+            ]),
+            AstNode::For(variable, start, end, _, body) => nodes.extend([
+                mem::take(&mut **variable),
+                mem::take(&mut **start),
+                mem::take(&mut **end),
+                mem::take(&mut **body),
+            ]),
+            AstNode::Num(_) | AstNode::Var(_) | AstNode::NoOp | AstNode::Type(_) => {}
+        }
+    }
+}
+
+// Trees are taken apart with a loop instead of recursion, so dropping a
+// long run like `1 + 1 + ... + 1`, whose tree is as deep as the run is
+// long, can't exhaust the stack.
+impl Drop for AstNode {
+    fn drop(&mut self) {
+        let mut nodes = vec![];
+        self.take_children(&mut nodes);
+        while let Some(mut node) = nodes.pop() {
+            node.take_children(&mut nodes);
+        }
+    }
+}
+
+// Writes `(head item item ...)`, leaving out the space when there are no items.
+fn list(f: &mut fmt::Formatter, head: &str, items: &[&dyn fmt::Display]) -> fmt::Result {
+    write!(f, "({}", head)?;
+    for item in items {
+        write!(f, " {}", item)?;
+    }
+    write!(f, ")")
+}
+
+// Joins nodes with spaces, for node lists nested inside a form.
+struct Nodes<'a>(&'a [AstNode]);
+
+impl fmt::Display for Nodes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, node) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", node)?;
+        }
+        Ok(())
+    }
+}
+
+// A compact S-expression, e.g. `(+ 3 (* 5 2))` for `3 + 5 * 2`. Operators
+// appear as written and empty statements as `()`.
+impl fmt::Display for AstNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AstNode::BinaryOp(left, right, token) => list(f, &token.lexeme, &[left, right]),
+            AstNode::Num(AstType::Integer(value)) => write!(f, "{}", value),
+            AstNode::Num(AstType::BigInt(value)) => write!(f, "{}", value),
+            AstNode::Num(AstType::Real(value)) => write!(f, "{:?}", value),
+            AstNode::Num(AstType::Boolean(true)) => write!(f, "TRUE"),
+            AstNode::Num(AstType::Boolean(false)) => write!(f, "FALSE"),
+            AstNode::Num(AstType::Str(value)) => write!(f, "'{}'", value.replace('\'', "''")),
+            AstNode::Num(AstType::Char(value)) => write!(f, "#{}", *value as u32),
+            AstNode::UnaryOp(operand, token) => list(f, &token.lexeme, &[operand]),
+            AstNode::Var(token) => write!(f, "{}", token.value),
+            AstNode::Assign(left, right, _) => list(f, ":=", &[left, right]),
+            AstNode::Compound(nodes) => list(f, "begin", &[&Nodes(nodes)]),
+            AstNode::NoOp => write!(f, "()"),
+            AstNode::Program(name, block) => list(f, "program", &[name, block]),
+            AstNode::Block(declarations, compound_statement) if declarations.is_empty() => {
+                list(f, "block", &[compound_statement])
+            }
+            AstNode::Block(declarations, compound_statement) => {
+                list(f, "block", &[&Nodes(declarations), compound_statement])
+            }
+            AstNode::VarDecl(var, type_node) => list(f, "var", &[var, type_node]),
+            AstNode::ArrayDecl(var, low, high, type_node) => {
+                list(f, "array", &[var, low, high, type_node])
+            }
+            AstNode::Index(token, index) => list(f, "index", &[&token.value, index]),
+            AstNode::ConstDecl(name, value) => list(f, "const", &[name, value]),
+            AstNode::Type(token) => write!(f, "{}", token.lexeme),
+            AstNode::FunctionCall(name, args) | AstNode::ProcedureCall(name, args)
+                if args.is_empty() =>
+            {
+                list(f, name, &[])
+            }
+            AstNode::FunctionCall(name, args) | AstNode::ProcedureCall(name, args) => {
+                list(f, name, &[&Nodes(args)])
+            }
+            AstNode::Formatted(value, width, precision) => match **precision {
+                AstNode::NoOp => list(f, ":", &[value, width]),
+                _ => list(f, ":", &[value, width, precision]),
+            },
+            AstNode::If(condition, then_branch, else_branch) => match **else_branch {
+                AstNode::NoOp => list(f, "if", &[condition, then_branch]),
+                _ => list(f, "if", &[condition, then_branch, else_branch]),
+            },
+            AstNode::IfExpr(condition, then_expr, else_expr) => {
+                list(f, "if", &[condition, then_expr, else_expr])
+            }
+            AstNode::While(condition, body) => list(f, "while", &[condition, body]),
+            AstNode::Repeat(body, condition) => {
+                write!(f, "(repeat {} ", Nodes(body))?;
+                list(f, "until", &[condition])?;
+                write!(f, ")")
+            }
+            AstNode::For(variable, start, end, direction, body) => {
+                let direction = match direction {
+                    TokenKind::Downto => "downto",
+                    _ => "to",
+                };
+                list(f, "for", &[variable, start, &direction, end, body])
+            }
+            AstNode::ProcedureDecl(name, params, block) => {
+                write!(f, "(procedure {} (", name)?;
+                write!(f, "{}) {})", Nodes(params), block)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    fn display(source: &str) -> String {
+        parse(source).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_display_expressions() {
+        assert_eq!(display("3 + 5 * 2"), "(+ 3 (* 5 2))");
+        assert_eq!(display("-(1.5 - x) DIV 2"), "(DIV (- (- 1.5 x)) 2)");
+        assert_eq!(
+            display("NOT done OR sqrt(4) > 1"),
+            "(OR (NOT done) (> (sqrt 4) 1))"
+        );
+        assert_eq!(display("format(x:8:2, y:4)"), "(format (: x 8 2) (: y 4))");
+    }
+
+    #[test]
+    fn test_display_statements() {
+        assert_eq!(
+            display("BEGIN a := 5; b := a * 2; END."),
+            "(begin (:= a 5) (:= b (* a 2)) ())"
+        );
+        assert_eq!(
+            display("PROGRAM P; VAR x : INTEGER; BEGIN FOR x := 3 DOWNTO 1 DO writeln(x) END."),
+            "(program P (block (var x INTEGER) (begin (for x 3 downto 1 (writeln x)))))"
+        );
+        assert_eq!(
+            display("BEGIN IF a THEN b := 1; REPEAT b := b + 1 UNTIL b > 3 END."),
+            "(begin (if a (:= b 1)) (repeat (:= b (+ b 1)) (until (> b 3))))"
+        );
+    }
 }
@@ -0,0 +1,227 @@
+use crate::interpreter::{BigInt, InterpreterType, MAX_FIELD_WIDTH};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatOptions {
+    pub base: u32,
+    pub precision: Option<usize>,
+    pub grouping: bool,
+    pub scientific: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions {
+            base: 10,
+            precision: None,
+            grouping: false,
+            scientific: false,
+        }
+    }
+}
+
+impl FormatOptions {
+    // Integers honor `base` and `grouping` (decimal only), reals honor
+    // `precision`, `scientific` and `grouping`. In scientific mode the
+    // precision controls the digits of the mantissa.
+    pub fn format(&self, value: &InterpreterType) -> String {
+        match value {
+            InterpreterType::Integer(value) => self.format_integer(*value),
+            InterpreterType::Real(value) => self.format_real(*value),
+            InterpreterType::BigInt(value) => self.format_big_integer(value),
+            InterpreterType::Boolean(value) => InterpreterType::Boolean(*value).to_string(),
+            InterpreterType::Str(value) => value.clone(),
+            InterpreterType::Char(value) => value.to_string(),
+            InterpreterType::Array(..) => value.to_string(),
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "base" => match value.parse::<u32>() {
+                Ok(base) if [2, 8, 10, 16].contains(&base) => self.base = base,
+                _ => return Err(format!("Invalid base: {}", value)),
+            },
+            "precision" => match value {
+                "off" => self.precision = None,
+                // Bounded like the decimals of a written value, which
+                // format! can't pad past u16::MAX
+                _ => match value.parse::<usize>() {
+                    Ok(precision) if precision <= MAX_FIELD_WIDTH as usize => {
+                        self.precision = Some(precision)
+                    }
+                    _ => return Err(format!("Invalid precision: {}", value)),
+                },
+            },
+            "grouping" => self.grouping = parse_switch(value)?,
+            "sci" => self.scientific = parse_switch(value)?,
+            _ => return Err(format!("Unknown setting: {}", name)),
+        }
+        Ok(())
+    }
+
+    fn format_integer(&self, value: i32) -> String {
+        let sign = if value < 0 { "-" } else { "" };
+        let magnitude = value.unsigned_abs();
+        let digits = match self.base {
+            2 => format!("{:b}", magnitude),
+            8 => format!("{:o}", magnitude),
+            16 => format!("{:x}", magnitude),
+            _ => magnitude.to_string(),
+        };
+        self.format_digits(sign, &digits)
+    }
+
+    fn format_big_integer(&self, value: &BigInt) -> String {
+        let text = value.to_str_radix(self.base);
+        match text.strip_prefix('-') {
+            Some(digits) => self.format_digits("-", digits),
+            None => self.format_digits("", &text),
+        }
+    }
+
+    // Puts the sign and the prefix for `base` in front of the digits of an
+    // integer, grouping them when they are decimal.
+    fn format_digits(&self, sign: &str, digits: &str) -> String {
+        match self.base {
+            2 => format!("{}0b{}", sign, digits),
+            8 => format!("{}0o{}", sign, digits),
+            16 => format!("{}0x{}", sign, digits),
+            _ if self.grouping => format!("{}{}", sign, group_digits(digits)),
+            _ => format!("{}{}", sign, digits),
+        }
+    }
+
+    fn format_real(&self, value: f64) -> String {
+        if self.scientific {
+            return match self.precision {
+                Some(precision) => format!("{:.*e}", precision, value),
+                None => format!("{:e}", value),
+            };
+        }
+        let text = match self.precision {
+            Some(precision) => format!("{:.*}", precision, value),
+            None => InterpreterType::Real(value).to_string(),
+        };
+        // Very large and very small reals already print in exponent form,
+        // where there are no thousands to separate
+        if !self.grouping || !value.is_finite() || text.contains('e') {
+            return text;
+        }
+        let (sign, text) = match text.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", text.as_str()),
+        };
+        match text.split_once('.') {
+            Some((whole, fraction)) => format!("{}{}.{}", sign, group_digits(whole), fraction),
+            None => format!("{}{}", sign, group_digits(text)),
+        }
+    }
+}
+
+fn parse_switch(value: &str) -> Result<bool, String> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("Expected on or off, found: {}", value)),
+    }
+}
+
+fn group_digits(digits: &str) -> String {
+    let mut result = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            result.push(',');
+        }
+        result.push(digit);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_format() {
+        let options = FormatOptions::default();
+        assert_eq!(options.format(&InterpreterType::Integer(1234)), "1234");
+        assert_eq!(options.format(&InterpreterType::Real(2.5)), "2.5");
+    }
+
+    #[test]
+    fn test_hexadecimal_format() {
+        let mut options = FormatOptions::default();
+        options.set("base", "16").unwrap();
+        assert_eq!(options.format(&InterpreterType::Integer(255)), "0xff");
+        assert_eq!(options.format(&InterpreterType::Integer(-255)), "-0xff");
+    }
+
+    #[test]
+    fn test_grouping_format() {
+        let mut options = FormatOptions::default();
+        options.set("grouping", "on").unwrap();
+        assert_eq!(
+            options.format(&InterpreterType::Integer(-1234567)),
+            "-1,234,567"
+        );
+        assert_eq!(options.format(&InterpreterType::Real(1234.5)), "1,234.5");
+        assert_eq!(options.format(&InterpreterType::Real(1e20)), "1e20");
+        assert_eq!(options.format(&InterpreterType::Real(-1e20)), "-1e20");
+        assert_eq!(options.format(&InterpreterType::Real(1e-7)), "1e-7");
+        assert_eq!(options.format(&InterpreterType::Real(1e16)), "1e16");
+        assert_eq!(
+            options.format(&InterpreterType::Real(1e15)),
+            "1,000,000,000,000,000.0"
+        );
+    }
+
+    #[test]
+    fn test_precision_format() {
+        let mut options = FormatOptions::default();
+        options.set("precision", "2").unwrap();
+        assert_eq!(
+            options.format(&InterpreterType::Real(std::f64::consts::PI)),
+            "3.14"
+        );
+        assert_eq!(options.format(&InterpreterType::Integer(7)), "7");
+        options.set("precision", "off").unwrap();
+        assert_eq!(options.format(&InterpreterType::Real(1.23456)), "1.23456");
+    }
+
+    #[test]
+    fn test_scientific_format_with_precision() {
+        let mut options = FormatOptions::default();
+        options.set("precision", "2").unwrap();
+        options.set("sci", "on").unwrap();
+        assert_eq!(options.format(&InterpreterType::Real(12345.678)), "1.23e4");
+        assert_eq!(options.format(&InterpreterType::Integer(12345)), "12345");
+    }
+
+    #[test]
+    fn test_big_integer_format() {
+        let big = InterpreterType::BigInt(BigInt::from(i64::MIN));
+        let mut options = FormatOptions::default();
+        assert_eq!(options.format(&big), "-9223372036854775808");
+        options.set("grouping", "on").unwrap();
+        assert_eq!(options.format(&big), "-9,223,372,036,854,775,808");
+        options.set("base", "16").unwrap();
+        assert_eq!(options.format(&big), "-0x8000000000000000");
+        options.set("base", "2").unwrap();
+        let big = InterpreterType::BigInt(BigInt::from(1 << 40));
+        assert_eq!(options.format(&big), format!("0b1{}", "0".repeat(40)));
+    }
+
+    #[test]
+    fn test_invalid_setting() {
+        let mut options = FormatOptions::default();
+        assert!(options.set("base", "3").is_err());
+        assert!(options.set("sci", "maybe").is_err());
+        assert!(options.set("colour", "on").is_err());
+        assert_eq!(
+            options.set("precision", "18446744073709551615"),
+            Err("Invalid precision: 18446744073709551615".to_string())
+        );
+        assert!(options.set("precision", "1025").is_err());
+        assert_eq!(options.precision, None);
+    }
+}
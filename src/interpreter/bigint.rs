@@ -0,0 +1,371 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Neg;
+
+// Sign-magnitude arbitrary-precision integer. The magnitude is stored as
+// little-endian base 2^32 limbs without trailing zero limbs, so zero is an
+// empty vector and is never negative.
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    fn from_parts(negative: bool, magnitude: Vec<u32>) -> BigInt {
+        let mut value = BigInt {
+            negative,
+            magnitude,
+        };
+        while value.magnitude.last() == Some(&0) {
+            value.magnitude.pop();
+        }
+        if value.magnitude.is_empty() {
+            value.negative = false;
+        }
+        value
+    }
+
+    // The value of a run of ASCII decimal digits, as in an integer literal,
+    // or None if there is anything else in it
+    pub fn from_digits(digits: &str) -> Option<BigInt> {
+        if digits.is_empty() {
+            return None;
+        }
+        let ten = BigInt::from(10);
+        digits.chars().try_fold(BigInt::from(0), |value, c| {
+            let digit = c.to_digit(10)?;
+            Some(value.mul(&ten).add(&BigInt::from(digit as i64)))
+        })
+    }
+
+    // The digits in base `radix`, from 2 to 16, with a `-` in front when
+    // negative.
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let mut digits = vec![];
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            let (quotient, remainder) = div_rem_small(&magnitude, radix);
+            digits.push(b"0123456789abcdef"[remainder as usize] as char);
+            magnitude = quotient;
+        }
+        if self.negative {
+            digits.push('-');
+        }
+        digits.into_iter().rev().collect()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn to_i32(&self) -> Option<i32> {
+        match self.magnitude.as_slice() {
+            [] => Some(0),
+            [limb] => {
+                let value = *limb as i64;
+                i32::try_from(if self.negative { -value } else { value }).ok()
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let value = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0.0, |acc, limb| acc * 4_294_967_296.0 + *limb as f64);
+        if self.negative {
+            -value
+        } else {
+            value
+        }
+    }
+
+    pub fn add(&self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(
+                self.negative,
+                add_magnitude(&self.magnitude, &other.magnitude),
+            );
+        }
+        match cmp_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::from_parts(
+                other.negative,
+                sub_magnitude(&other.magnitude, &self.magnitude),
+            ),
+            _ => BigInt::from_parts(
+                self.negative,
+                sub_magnitude(&self.magnitude, &other.magnitude),
+            ),
+        }
+    }
+
+    pub fn sub(&self, other: &BigInt) -> BigInt {
+        self.add(&-other.clone())
+    }
+
+    pub fn mul(&self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(
+            self.negative != other.negative,
+            mul_magnitude(&self.magnitude, &other.magnitude),
+        )
+    }
+
+    // Truncating division: the quotient rounds toward zero and the
+    // remainder takes the sign of the dividend, like i32's `/` and `%`.
+    pub fn div_rem(&self, other: &BigInt) -> Option<(BigInt, BigInt)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &other.magnitude);
+        Some((
+            BigInt::from_parts(self.negative != other.negative, quotient),
+            BigInt::from_parts(self.negative, remainder),
+        ))
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> BigInt {
+        let mut result = BigInt::from(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base);
+            }
+            base = base.mul(&base);
+            exponent >>= 1;
+        }
+        result
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> BigInt {
+        let magnitude = value.unsigned_abs();
+        BigInt::from_parts(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Neg for BigInt {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        let negative = !self.negative;
+        BigInt::from_parts(negative, self.magnitude)
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut chunks = vec![];
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            let (quotient, remainder) = div_rem_small(&magnitude, 1_000_000_000);
+            chunks.push(remainder);
+            magnitude = quotient;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+fn trim(mut magnitude: Vec<u32>) -> Vec<u32> {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    magnitude
+}
+
+fn cmp_magnitude(left: &[u32], right: &[u32]) -> Ordering {
+    left.len()
+        .cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(left.len().max(right.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..left.len().max(right.len()) {
+        let sum = *left.get(i).unwrap_or(&0) as u64 + *right.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    result.push(carry as u32);
+    trim(result)
+}
+
+// Requires `left >= right`.
+fn sub_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(left.len());
+    let mut borrow = 0i64;
+    for (i, limb) in left.iter().enumerate() {
+        let mut difference = *limb as i64 - *right.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        result.push(difference as u32);
+    }
+    trim(result)
+}
+
+fn mul_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; left.len() + right.len()];
+    for (i, a) in left.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, b) in right.iter().enumerate() {
+            let product = *a as u64 * *b as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + right.len()] = carry as u32;
+    }
+    trim(result)
+}
+
+fn div_rem_small(magnitude: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; magnitude.len()];
+    let mut remainder = 0u64;
+    for (i, limb) in magnitude.iter().enumerate().rev() {
+        let current = (remainder << 32) | *limb as u64;
+        quotient[i] = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    (trim(quotient), remainder as u32)
+}
+
+// Binary long division, one bit of the dividend at a time.
+fn div_rem_magnitude(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder: Vec<u32> = vec![];
+    for i in (0..dividend.len() * 32).rev() {
+        let bit = (dividend[i / 32] >> (i % 32)) & 1;
+        remainder = shift_left_one(&remainder, bit);
+        if cmp_magnitude(&remainder, divisor) != Ordering::Less {
+            remainder = sub_magnitude(&remainder, divisor);
+            quotient[i / 32] |= 1 << (i % 32);
+        }
+    }
+    (trim(quotient), remainder)
+}
+
+fn shift_left_one(magnitude: &[u32], bit: u32) -> Vec<u32> {
+    let mut result = Vec::with_capacity(magnitude.len() + 1);
+    let mut carry = bit;
+    for limb in magnitude {
+        result.push((limb << 1) | carry);
+        carry = limb >> 31;
+    }
+    result.push(carry);
+    trim(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(BigInt::from(0).to_string(), "0");
+        assert_eq!(BigInt::from(-42).to_string(), "-42");
+        assert_eq!(BigInt::from(i64::MAX).to_string(), "9223372036854775807");
+    }
+
+    #[test]
+    fn test_factorial() {
+        let mut result = BigInt::from(1);
+        for i in 1..=30 {
+            result = result.mul(&BigInt::from(i));
+        }
+        assert_eq!(result.to_string(), "265252859812191058636308480000000");
+    }
+
+    #[test]
+    fn test_add_and_sub_with_signs() {
+        let big = BigInt::from(i64::MAX);
+        let sum = big.add(&BigInt::from(1));
+        assert_eq!(sum.to_string(), "9223372036854775808");
+        assert_eq!(sum.sub(&big), BigInt::from(1));
+        assert_eq!(BigInt::from(3).sub(&BigInt::from(5)), BigInt::from(-2));
+        assert_eq!(BigInt::from(-3).add(&BigInt::from(3)), BigInt::from(0));
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let value = BigInt::from(2).pow(100);
+        let (quotient, remainder) = value.div_rem(&BigInt::from(3)).unwrap();
+        assert_eq!(quotient.to_string(), "422550200076076467165567735125");
+        assert_eq!(remainder, BigInt::from(1));
+        let (quotient, remainder) = BigInt::from(-7).div_rem(&BigInt::from(2)).unwrap();
+        assert_eq!((quotient, remainder), (BigInt::from(-3), BigInt::from(-1)));
+        assert!(value.div_rem(&BigInt::from(0)).is_none());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let big = BigInt::from(2).pow(40);
+        assert!(BigInt::from(-3) < BigInt::from(2));
+        assert!(-big.clone() < BigInt::from(-3));
+        assert!(big > BigInt::from(i64::from(i32::MAX)));
+        assert!(BigInt::from(-2) > BigInt::from(-3));
+    }
+
+    #[test]
+    fn test_to_i32() {
+        assert_eq!(BigInt::from(-5).to_i32(), Some(-5));
+        assert_eq!(BigInt::from(i32::MIN as i64).to_i32(), Some(i32::MIN));
+        assert_eq!(BigInt::from(i32::MAX as i64 + 1).to_i32(), None);
+    }
+
+    #[test]
+    fn test_from_digits() {
+        let value = BigInt::from_digits("98765432109876543210").unwrap();
+        assert_eq!(value.to_string(), "98765432109876543210");
+        assert_eq!(BigInt::from_digits("007"), Some(BigInt::from(7)));
+        assert_eq!(BigInt::from_digits("1½"), None);
+        assert_eq!(BigInt::from_digits("12a"), None);
+        assert_eq!(BigInt::from_digits(""), None);
+    }
+
+    #[test]
+    fn test_to_str_radix() {
+        assert_eq!(BigInt::from(0).to_str_radix(2), "0");
+        assert_eq!(BigInt::from(-255).to_str_radix(16), "-ff");
+        assert_eq!(BigInt::from(8).to_str_radix(8), "10");
+        let big = BigInt::from(2).pow(70);
+        assert_eq!(big.to_str_radix(16), format!("4{}", "0".repeat(17)));
+        assert_eq!(big.to_str_radix(10), big.to_string());
+    }
+}
@@ -0,0 +1,120 @@
+use super::kind::{InterpreterType, NumericOps};
+
+pub fn call(name: &str, args: &[InterpreterType]) -> Result<InterpreterType, String> {
+    if let Some(value) = constant(name) {
+        expect_args::<0>(name, args)?;
+        return Ok(value);
+    }
+    match name {
+        "lerp" => {
+            let [a, b, t] = expect_args::<3>(name, args)?;
+            let (a, b, t) = (number(name, a)?, number(name, b)?, number(name, t)?);
+            Ok(InterpreterType::Real(a + (b - a) * t))
+        }
+        "clamp01" => {
+            let [x] = expect_args::<1>(name, args)?;
+            Ok(InterpreterType::Real(number(name, x)?.clamp(0.0, 1.0)))
+        }
+        "abs" => {
+            let [x] = expect_args::<1>(name, args)?;
+            if x.compare(&InterpreterType::Integer(0))?.is_lt() {
+                x.checked_neg()
+            } else {
+                Ok(x)
+            }
+        }
+        "sign" => {
+            let [x] = expect_args::<1>(name, args)?;
+            let sign = x.compare(&InterpreterType::Integer(0))? as i32;
+            Ok(match x {
+                InterpreterType::Real(_) => InterpreterType::Real(sign as f64),
+                _ => InterpreterType::Integer(sign),
+            })
+        }
+        // All four give an Integer; integer arguments come back unchanged
+        "trunc" | "round" | "floor" | "ceil" => {
+            let [x] = expect_args::<1>(name, args)?;
+            let x = match x {
+                InterpreterType::Real(x) => x,
+                InterpreterType::Integer(_) | InterpreterType::BigInt(_) => return Ok(x),
+                _ => return Err(format!("{} expects a number, found {}", name, x)),
+            };
+            let result = match name {
+                "trunc" => x.trunc(),
+                // Halves round away from zero, so round(2.5) is 3
+                "round" => x.round(),
+                "floor" => x.floor(),
+                _ => x.ceil(),
+            };
+            if result >= i32::MIN as f64 && result <= i32::MAX as f64 {
+                Ok(InterpreterType::Integer(result as i32))
+            } else {
+                Err("Integer overflow".to_string())
+            }
+        }
+        "ord" => {
+            let [x] = expect_args::<1>(name, args)?;
+            let c = match &x {
+                InterpreterType::Char(c) => *c,
+                // A one-character string literal like 'A' is a character too
+                InterpreterType::Str(value) if value.chars().count() == 1 => {
+                    value.chars().next().unwrap()
+                }
+                _ => return Err(format!("ord expects a character, found {}", x)),
+            };
+            Ok(InterpreterType::Integer(c as i32))
+        }
+        "chr" => {
+            let [x] = expect_args::<1>(name, args)?;
+            let code = match x {
+                InterpreterType::Integer(code) => code,
+                _ => return Err(format!("chr expects an integer, found {}", x)),
+            };
+            match u32::try_from(code).ok().and_then(char::from_u32) {
+                Some(c) => Ok(InterpreterType::Char(c)),
+                None => Err(format!("Character code out of range: {}", code)),
+            }
+        }
+        "sqrt" | "sin" | "cos" | "ln" | "exp" => {
+            let [x] = expect_args::<1>(name, args)?;
+            let x = number(name, x)?;
+            let result = match name {
+                "sqrt" if x >= 0.0 => x.sqrt(),
+                "ln" if x > 0.0 => x.ln(),
+                "sin" => x.sin(),
+                "cos" => x.cos(),
+                "exp" => x.exp(),
+                _ => return Err(format!("{} is undefined for {}", name, x)),
+            };
+            Ok(InterpreterType::Real(result))
+        }
+        _ => Err(format!("Unknown function: {}", name)),
+    }
+}
+
+// Builtins without arguments, which may also be written without the
+// parentheses, as in `2 * pi`. A variable of the same name hides them.
+pub fn constant(name: &str) -> Option<InterpreterType> {
+    match name {
+        "pi" => Some(InterpreterType::Real(std::f64::consts::PI)),
+        "e" => Some(InterpreterType::Real(std::f64::consts::E)),
+        _ => None,
+    }
+}
+
+fn number(name: &str, x: InterpreterType) -> Result<f64, String> {
+    if x.is_number() {
+        Ok(x.from::<f64>())
+    } else {
+        Err(format!("{} expects a number, found {}", name, x))
+    }
+}
+
+fn expect_args<const N: usize>(
+    name: &str,
+    args: &[InterpreterType],
+) -> Result<[InterpreterType; N], String> {
+    args.to_vec()
+        .try_into()
+        .map_err(|_| format!("{} expects {} argument(s), got {}", name, N, args.len()))
+}
@@ -1,101 +1,240 @@
-use std::ops::{Add, Div, Mul, Neg, Sub};
-#[derive(Debug, Clone, Copy)]
+use std::cmp::Ordering;
+use std::fmt;
+
+use super::bigint::BigInt;
+
+#[derive(Debug, Clone)]
 pub enum InterpreterType {
     Integer(i32),
     Real(f64),
+    BigInt(BigInt),
+    // Produced by comparisons; never promoted to or from a number
+    Boolean(bool),
+    // Only supports concatenation with `+` and comparison with other strings
+    Str(String),
+    // Behaves like a one-character string; `ord` gives its code, and adding
+    // or subtracting an integer moves it through the character codes
+    Char(char),
+    // The lowest index and the elements, which all have the declared type
+    Array(i32, Vec<InterpreterType>),
 }
 
-impl Neg for InterpreterType {
-    type Output = Self;
-
-    fn neg(self) -> Self::Output {
+impl fmt::Display for InterpreterType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            InterpreterType::Integer(value) => InterpreterType::Integer(-value),
-            InterpreterType::Real(value) => InterpreterType::Real(-value),
+            InterpreterType::Integer(value) => write!(f, "{}", value),
+            InterpreterType::Real(value) => write!(f, "{:?}", value),
+            InterpreterType::BigInt(value) => write!(f, "{}", value),
+            InterpreterType::Boolean(true) => write!(f, "TRUE"),
+            InterpreterType::Boolean(false) => write!(f, "FALSE"),
+            InterpreterType::Str(value) => write!(f, "{}", value),
+            InterpreterType::Char(value) => write!(f, "{}", value),
+            InterpreterType::Array(_, elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.literal()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
         }
     }
 }
 
-impl Add for InterpreterType {
-    type Output = Self;
+// Arithmetic shared by every numeric backend. Implementations report
+// failures such as overflow as errors instead of panicking, so the
+// interpreter can surface them to the user.
+pub trait NumericOps: Sized {
+    fn checked_add(self, other: Self) -> Result<Self, String>;
+    fn checked_sub(self, other: Self) -> Result<Self, String>;
+    fn checked_mul(self, other: Self) -> Result<Self, String>;
+    fn checked_div(self, other: Self) -> Result<Self, String>;
+    fn checked_neg(self) -> Result<Self, String>;
+}
+
+enum Operands {
+    Integers(i32, i32),
+    BigInts(BigInt, BigInt),
+    Reals(f64, f64),
+}
 
-    fn add(self, other: Self) -> Self::Output {
+impl InterpreterType {
+    fn promote(self, other: Self) -> Result<Operands, String> {
         match (self, other) {
+            (value, _) | (_, value) if !value.is_number() => Err(expected_number(&value)),
             (InterpreterType::Integer(left), InterpreterType::Integer(right)) => {
-                InterpreterType::Integer(left + right)
-            }
-            (InterpreterType::Real(left), InterpreterType::Real(right)) => {
-                InterpreterType::Real(left + right)
-            }
-            (InterpreterType::Integer(left), InterpreterType::Real(right)) => {
-                InterpreterType::Real(left as f64 + right)
+                Ok(Operands::Integers(left, right))
             }
-            (InterpreterType::Real(left), InterpreterType::Integer(right)) => {
-                InterpreterType::Real(left + right as f64)
+            (left @ InterpreterType::Real(_), right) | (left, right @ InterpreterType::Real(_)) => {
+                Ok(Operands::Reals(left.from::<f64>(), right.from::<f64>()))
             }
+            (left, right) => Ok(Operands::BigInts(left.to_big(), right.to_big())),
         }
     }
-}
 
-impl Sub for InterpreterType {
-    type Output = Self;
+    fn to_big(&self) -> BigInt {
+        match self {
+            InterpreterType::Integer(value) => BigInt::from(*value as i64),
+            InterpreterType::Real(value) => BigInt::from(*value as i64),
+            InterpreterType::BigInt(value) => value.clone(),
+            InterpreterType::Boolean(value) => BigInt::from(*value as i64),
+            InterpreterType::Str(_) => BigInt::from(0),
+            InterpreterType::Char(value) => BigInt::from(*value as i64),
+            InterpreterType::Array(..) => BigInt::from(0),
+        }
+    }
 
-    fn sub(self, other: Self) -> Self::Output {
-        match (self, other) {
-            (InterpreterType::Integer(left), InterpreterType::Integer(right)) => {
-                InterpreterType::Integer(left - right)
-            }
-            (InterpreterType::Real(left), InterpreterType::Real(right)) => {
-                InterpreterType::Real(left - right)
-            }
-            (InterpreterType::Integer(left), InterpreterType::Real(right)) => {
-                InterpreterType::Real(left as f64 - right)
-            }
-            (InterpreterType::Real(left), InterpreterType::Integer(right)) => {
-                InterpreterType::Real(left - right as f64)
-            }
+    pub fn is_number(&self) -> bool {
+        matches!(
+            self,
+            InterpreterType::Integer(_) | InterpreterType::Real(_) | InterpreterType::BigInt(_)
+        )
+    }
+
+    // Strings and characters, which join with `+` and compare as text.
+    fn text(&self) -> Option<String> {
+        match self {
+            InterpreterType::Str(value) => Some(value.clone()),
+            InterpreterType::Char(value) => Some(value.to_string()),
+            _ => None,
         }
     }
-}
 
-impl Mul for InterpreterType {
-    type Output = Self;
+    // The character of a CHAR value, for ordinal arithmetic like `c + 1`.
+    // A literal like 'A' is a string, so it joins other strings instead.
+    fn ordinal(&self) -> Option<char> {
+        match self {
+            InterpreterType::Char(value) => Some(*value),
+            _ => None,
+        }
+    }
 
-    fn mul(self, other: Self) -> Self::Output {
-        match (self, other) {
-            (InterpreterType::Integer(left), InterpreterType::Integer(right)) => {
-                InterpreterType::Integer(left * right)
-            }
-            (InterpreterType::Real(left), InterpreterType::Real(right)) => {
-                InterpreterType::Real(left * right)
-            }
-            (InterpreterType::Integer(left), InterpreterType::Real(right)) => {
-                InterpreterType::Real(left as f64 * right)
-            }
-            (InterpreterType::Real(left), InterpreterType::Integer(right)) => {
-                InterpreterType::Real(left * right as f64)
-            }
+    fn offset(&self) -> Option<i64> {
+        match self {
+            InterpreterType::Integer(value) => Some(*value as i64),
+            InterpreterType::BigInt(value) => value.to_i32().map(i64::from),
+            _ => None,
+        }
+    }
+
+    // Like Display, but quotes strings so they stand out in error messages.
+    fn literal(&self) -> String {
+        match self {
+            InterpreterType::Str(value) => format!("'{}'", value),
+            InterpreterType::Char(value) => format!("'{}'", value),
+            value => value.to_string(),
+        }
+    }
+
+    // Moves integers onto the arbitrary-precision backend; results are
+    // narrowed back to `Integer` whenever they fit.
+    pub fn widen(self) -> Self {
+        match self {
+            InterpreterType::Integer(value) => InterpreterType::BigInt(BigInt::from(value as i64)),
+            value => value,
         }
     }
 }
 
-impl Div for InterpreterType {
-    type Output = Self;
+fn narrow(value: BigInt) -> InterpreterType {
+    match value.to_i32() {
+        Some(value) => InterpreterType::Integer(value),
+        None => InterpreterType::BigInt(value),
+    }
+}
 
-    fn div(self, other: Self) -> Self::Output {
-        match (self, other) {
-            (InterpreterType::Integer(left), InterpreterType::Integer(right)) => {
-                InterpreterType::Real(left as f64 / right as f64)
-            }
-            (InterpreterType::Real(left), InterpreterType::Real(right)) => {
-                InterpreterType::Real(left / right)
+// The character `offset` places after `value`, or before it when negative.
+fn shift_char(value: char, offset: i64) -> Result<InterpreterType, String> {
+    let code = value as i64 + offset;
+    u32::try_from(code)
+        .ok()
+        .and_then(char::from_u32)
+        .map(InterpreterType::Char)
+        .ok_or_else(|| format!("Character code out of range: {}", code))
+}
+
+fn expected_number(value: &InterpreterType) -> String {
+    format!("Expected a number, found {}", value.literal())
+}
+
+fn overflow(value: Option<i32>) -> Result<InterpreterType, String> {
+    value
+        .map(InterpreterType::Integer)
+        .ok_or_else(|| "Integer overflow".to_string())
+}
+
+impl NumericOps for InterpreterType {
+    fn checked_add(self, other: Self) -> Result<Self, String> {
+        // Pascal's ordinal arithmetic: a character plus an integer is the
+        // character that many places on, but two characters don't add up
+        if let (Some(value), Some(offset)) = (self.ordinal(), other.offset()) {
+            return shift_char(value, offset);
+        }
+        if let (Some(offset), Some(value)) = (self.offset(), other.ordinal()) {
+            return shift_char(value, offset);
+        }
+        if let (InterpreterType::Char(_), InterpreterType::Char(_)) = (&self, &other) {
+            return Err(format!(
+                "Cannot add {} and {}",
+                self.literal(),
+                other.literal()
+            ));
+        }
+        match (self.text(), other.text()) {
+            (Some(left), Some(right)) => return Ok(InterpreterType::Str(left + &right)),
+            (Some(_), None) | (None, Some(_)) => {
+                return Err(format!(
+                    "Cannot concatenate {} and {}",
+                    self.literal(),
+                    other.literal()
+                ))
             }
-            (InterpreterType::Integer(left), InterpreterType::Real(right)) => {
-                InterpreterType::Real(left as f64 / right)
+            (None, None) => {}
+        }
+        match self.promote(other)? {
+            Operands::Integers(left, right) => overflow(left.checked_add(right)),
+            Operands::BigInts(left, right) => Ok(narrow(left.add(&right))),
+            Operands::Reals(left, right) => Ok(InterpreterType::Real(left + right)),
+        }
+    }
+
+    fn checked_sub(self, other: Self) -> Result<Self, String> {
+        // Two characters are the distance between their codes
+        if let (Some(left), Some(right)) = (self.ordinal(), other.ordinal()) {
+            return Ok(InterpreterType::Integer(left as i32 - right as i32));
+        }
+        if let (Some(value), Some(offset)) = (self.ordinal(), other.offset()) {
+            return shift_char(value, -offset);
+        }
+        match self.promote(other)? {
+            Operands::Integers(left, right) => overflow(left.checked_sub(right)),
+            Operands::BigInts(left, right) => Ok(narrow(left.sub(&right))),
+            Operands::Reals(left, right) => Ok(InterpreterType::Real(left - right)),
+        }
+    }
+
+    fn checked_mul(self, other: Self) -> Result<Self, String> {
+        match self.promote(other)? {
+            Operands::Integers(left, right) => overflow(left.checked_mul(right)),
+            Operands::BigInts(left, right) => Ok(narrow(left.mul(&right))),
+            Operands::Reals(left, right) => Ok(InterpreterType::Real(left * right)),
+        }
+    }
+
+    fn checked_div(self, other: Self) -> Result<Self, String> {
+        match self.promote(other)? {
+            Operands::Integers(left, right) => {
+                Ok(InterpreterType::Real(left as f64 / right as f64))
             }
-            (InterpreterType::Real(left), InterpreterType::Integer(right)) => {
-                InterpreterType::Real(left / right as f64)
+            Operands::BigInts(left, right) => {
+                Ok(InterpreterType::Real(left.to_f64() / right.to_f64()))
             }
+            Operands::Reals(left, right) => Ok(InterpreterType::Real(left / right)),
+        }
+    }
+
+    fn checked_neg(self) -> Result<Self, String> {
+        match self {
+            InterpreterType::Integer(value) => overflow(value.checked_neg()),
+            InterpreterType::Real(value) => Ok(InterpreterType::Real(-value)),
+            InterpreterType::BigInt(value) => Ok(narrow(-value)),
+            value => Err(expected_number(&value)),
         }
     }
 }
@@ -105,6 +244,11 @@ impl From<InterpreterType> for f64 {
         match value {
             InterpreterType::Integer(value) => value as f64,
             InterpreterType::Real(value) => value,
+            InterpreterType::BigInt(value) => value.to_f64(),
+            InterpreterType::Boolean(value) => value as i32 as f64,
+            InterpreterType::Str(_) => 0.0,
+            InterpreterType::Char(value) => value as u32 as f64,
+            InterpreterType::Array(..) => 0.0,
         }
     }
 }
@@ -114,31 +258,428 @@ impl From<InterpreterType> for i32 {
         match value {
             InterpreterType::Integer(value) => value,
             InterpreterType::Real(value) => value as i32,
+            InterpreterType::BigInt(value) => value.to_f64() as i32,
+            InterpreterType::Boolean(value) => value as i32,
+            InterpreterType::Str(_) => 0,
+            InterpreterType::Char(value) => value as i32,
+            InterpreterType::Array(..) => 0,
         }
     }
 }
 
 impl InterpreterType {
+    pub fn is_zero(&self) -> bool {
+        match self {
+            InterpreterType::Integer(value) => *value == 0,
+            InterpreterType::Real(value) => *value == 0.0,
+            InterpreterType::BigInt(value) => value.is_zero(),
+            InterpreterType::Boolean(_)
+            | InterpreterType::Str(_)
+            | InterpreterType::Char(_)
+            | InterpreterType::Array(..) => false,
+        }
+    }
+
+    // Conditions of IF, WHILE and UNTIL take booleans only, so `IF 1` is an
+    // error rather than true.
+    pub fn is_truthy(&self) -> Result<bool, String> {
+        match self {
+            InterpreterType::Boolean(value) => Ok(*value),
+            value => Err(format!(
+                "Condition must be a boolean, found {}",
+                value.literal()
+            )),
+        }
+    }
+
+    // Logical operators take booleans only, so `3 AND TRUE` is an error.
+    pub fn as_bool(&self) -> Result<bool, String> {
+        match self {
+            InterpreterType::Boolean(value) => Ok(*value),
+            value => Err(format!("Expected a boolean, found {}", value.literal())),
+        }
+    }
+
+    // Pascal reuses the logical operators on integers, where they work bit
+    // by bit. Both operands must be booleans or both integers.
+    pub fn and(self, other: Self) -> Result<Self, String> {
+        self.bitwise(
+            other,
+            "AND",
+            |left, right| left & right,
+            |left, right| left & right,
+        )
+    }
+
+    pub fn or(self, other: Self) -> Result<Self, String> {
+        self.bitwise(
+            other,
+            "OR",
+            |left, right| left | right,
+            |left, right| left | right,
+        )
+    }
+
+    pub fn xor(self, other: Self) -> Result<Self, String> {
+        self.bitwise(
+            other,
+            "XOR",
+            |left, right| left ^ right,
+            |left, right| left ^ right,
+        )
+    }
+
+    fn bitwise(
+        self,
+        other: Self,
+        name: &str,
+        logical: fn(bool, bool) -> bool,
+        bits: fn(i32, i32) -> i32,
+    ) -> Result<Self, String> {
+        match (self, other) {
+            (InterpreterType::Boolean(left), InterpreterType::Boolean(right)) => {
+                Ok(InterpreterType::Boolean(logical(left, right)))
+            }
+            (InterpreterType::Integer(left), InterpreterType::Integer(right)) => {
+                Ok(InterpreterType::Integer(bits(left, right)))
+            }
+            (left, right) => Err(format!(
+                "Cannot apply {} to {} and {}",
+                name,
+                left.literal(),
+                right.literal()
+            )),
+        }
+    }
+
+    pub fn invert(self) -> Result<Self, String> {
+        match self {
+            InterpreterType::Boolean(value) => Ok(InterpreterType::Boolean(!value)),
+            InterpreterType::Integer(value) => Ok(InterpreterType::Integer(!value)),
+            value => Err(format!("Cannot apply NOT to {}", value.literal())),
+        }
+    }
+
+    // Bits shifted out are lost; shifting by 32 or more is an error.
+    pub fn shift_left(self, other: Self) -> Result<Self, String> {
+        self.shift(other, "SHL", i32::checked_shl)
+    }
+
+    pub fn shift_right(self, other: Self) -> Result<Self, String> {
+        self.shift(other, "SHR", i32::checked_shr)
+    }
+
+    fn shift(
+        self,
+        other: Self,
+        name: &str,
+        shift: fn(i32, u32) -> Option<i32>,
+    ) -> Result<Self, String> {
+        match (self, other) {
+            (InterpreterType::Integer(value), InterpreterType::Integer(amount)) => {
+                match u32::try_from(amount)
+                    .ok()
+                    .and_then(|amount| shift(value, amount))
+                {
+                    Some(value) => Ok(InterpreterType::Integer(value)),
+                    None => Err(format!("Shift amount out of range: {}", amount)),
+                }
+            }
+            (left, right) => Err(format!(
+                "Cannot apply {} to {} and {}",
+                name,
+                left.literal(),
+                right.literal()
+            )),
+        }
+    }
+
+    // Numbers compare after the usual promotion; booleans only compare with
+    // booleans, and strings and characters with each other.
+    pub fn compare(&self, other: &Self) -> Result<Ordering, String> {
+        let incomparable = || format!("Cannot compare {} and {}", self.literal(), other.literal());
+        if let (Some(left), Some(right)) = (self.text(), other.text()) {
+            return Ok(left.cmp(&right));
+        }
+        match (self, other) {
+            (InterpreterType::Boolean(left), InterpreterType::Boolean(right)) => {
+                Ok(left.cmp(right))
+            }
+            (left, _) | (_, left) if !left.is_number() => Err(incomparable()),
+            (left, right) => match left.clone().promote(right.clone())? {
+                Operands::Integers(left, right) => Ok(left.cmp(&right)),
+                Operands::BigInts(left, right) => Ok(left.cmp(&right)),
+                Operands::Reals(left, right) => left.partial_cmp(&right).ok_or_else(incomparable),
+            },
+        }
+    }
+
+    // Unlike `From<InterpreterType> for i32`, this refuses to truncate.
+    pub fn as_index(&self) -> Result<i64, String> {
+        match self {
+            InterpreterType::Integer(value) => Ok(*value as i64),
+            InterpreterType::Real(value) if value.fract() == 0.0 => Ok(*value as i64),
+            InterpreterType::BigInt(value) => match value.to_i32() {
+                Some(value) => Ok(value as i64),
+                None => Err(format!("Index out of range: {}", value)),
+            },
+            value => Err(format!("Invalid index: {}", value)),
+        }
+    }
+
     pub fn from<T>(&self) -> T
     where
         T: From<InterpreterType>,
     {
-        T::from(*self)
+        T::from(self.clone())
     }
-    pub fn integer_div(self, other: Self) -> Self {
-        match (self, other) {
-            (InterpreterType::Integer(left), InterpreterType::Integer(right)) => {
-                InterpreterType::Integer(left / right)
+    // DIV is the exact quotient truncated toward zero, whatever the operand
+    // types: 7 DIV 2 and 7.5 DIV 2 are both 3, and -7 DIV 2 is -3. The
+    // operands are never truncated before dividing.
+    pub fn integer_div(self, other: Self) -> Result<Self, String> {
+        let division_by_zero = || "Division by zero".to_string();
+        match self.promote(other)? {
+            Operands::Integers(_, 0) => Err(division_by_zero()),
+            Operands::Integers(left, right) => overflow(left.checked_div(right)),
+            Operands::BigInts(left, right) => match left.div_rem(&right) {
+                Some((quotient, _)) => Ok(narrow(quotient)),
+                None => Err(division_by_zero()),
+            },
+            Operands::Reals(_, 0.0) => Err(division_by_zero()),
+            Operands::Reals(left, right) => {
+                let quotient = (left / right).trunc();
+                if quotient >= i32::MIN as f64 && quotient <= i32::MAX as f64 {
+                    Ok(InterpreterType::Integer(quotient as i32))
+                } else {
+                    Err("Integer overflow".to_string())
+                }
             }
-            (InterpreterType::Real(left), InterpreterType::Real(right)) => {
-                InterpreterType::Integer((left as i32 / right as i32) as i32)
-            }
-            (InterpreterType::Integer(left), InterpreterType::Real(right)) => {
-                InterpreterType::Integer((left / right as i32) as i32)
+        }
+    }
+    // Pascal's MOD: the remainder takes the sign of the dividend, and reals
+    // are rejected rather than silently truncated.
+    pub fn modulo(self, other: Self) -> Result<Self, String> {
+        match self.promote(other)? {
+            Operands::Integers(left, right) => overflow(left.checked_rem(right)),
+            Operands::BigInts(left, right) => match left.div_rem(&right) {
+                Some((_, remainder)) => Ok(narrow(remainder)),
+                None => Err("Division by zero".to_string()),
+            },
+            Operands::Reals(..) => Err("MOD expects integer operands".to_string()),
+        }
+    }
+    pub fn pow(self, other: Self) -> Result<Self, String> {
+        let real = |left: f64, right: f64| match left.powf(right) {
+            // Such as a fractional power of a negative number
+            result if result.is_nan() => Err(format!("{} ** {} is undefined", left, right)),
+            result => Ok(InterpreterType::Real(result)),
+        };
+        Ok(match (self, other) {
+            (value, _) | (_, value) if !value.is_number() => return Err(expected_number(&value)),
+            // Overflows like the other integer operators rather than
+            // quietly becoming a real
+            (InterpreterType::Integer(left), InterpreterType::Integer(right)) if right >= 0 => {
+                overflow(left.checked_pow(right as u32))?
             }
-            (InterpreterType::Real(left), InterpreterType::Integer(right)) => {
-                InterpreterType::Integer((left as i32 / right) as i32)
+            (InterpreterType::BigInt(left), right @ InterpreterType::BigInt(_))
+            | (InterpreterType::BigInt(left), right @ InterpreterType::Integer(_)) => {
+                match right.to_big().to_i32() {
+                    Some(exponent) if exponent >= 0 => narrow(left.pow(exponent as u32)),
+                    _ => real(left.to_f64(), right.from::<f64>())?,
+                }
             }
+            (left, right) => real(left.from::<f64>(), right.from::<f64>())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let cases = [
+            (InterpreterType::Integer(5), "5"),
+            (InterpreterType::Integer(-5), "-5"),
+            (InterpreterType::Real(2.5), "2.5"),
+            (InterpreterType::Real(3.0), "3.0"),
+            (
+                InterpreterType::BigInt(BigInt::from(1i64 << 40)),
+                "1099511627776",
+            ),
+            (InterpreterType::Boolean(true), "TRUE"),
+            (InterpreterType::Str("it's".to_string()), "it's"),
+            (InterpreterType::Char('x'), "x"),
+            (
+                InterpreterType::Array(
+                    1,
+                    vec![InterpreterType::Integer(1), InterpreterType::Integer(2)],
+                ),
+                "[1, 2]",
+            ),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(value.to_string(), expected);
         }
     }
+
+    #[test]
+    fn test_arithmetic_is_unchanged() {
+        let (two, three) = (InterpreterType::Integer(2), InterpreterType::Integer(3));
+        let half = InterpreterType::Real(0.5);
+        let check = |value: Result<InterpreterType, String>| value.unwrap().to_string();
+        assert_eq!(check(two.clone().checked_add(three.clone())), "5");
+        assert_eq!(check(two.clone().checked_sub(three.clone())), "-1");
+        assert_eq!(check(two.clone().checked_mul(three.clone())), "6");
+        assert_eq!(check(three.clone().checked_div(two.clone())), "1.5");
+        assert_eq!(check(two.clone().checked_add(half.clone())), "2.5");
+        assert_eq!(check(half.checked_mul(two.clone())), "1.0");
+        assert_eq!(check(two.clone().checked_neg()), "-2");
+        let product = three.checked_mul(two.clone());
+        assert_eq!(
+            check(product.and_then(|product| two.checked_add(product))),
+            "8"
+        );
+    }
+
+    #[test]
+    fn test_integer_overflow_is_an_error() {
+        let max = InterpreterType::Integer(i32::MAX);
+        let one = InterpreterType::Integer(1);
+        assert_eq!(max.checked_add(one).unwrap_err(), "Integer overflow");
+        assert!(InterpreterType::Integer(i32::MIN).checked_neg().is_err());
+    }
+
+    #[test]
+    fn test_widened_arithmetic_is_exact() {
+        let max = InterpreterType::Integer(i32::MAX).widen();
+        let product = max.clone().checked_mul(max).unwrap();
+        assert_eq!(product.to_string(), "4611686014132420609");
+        let quotient = product
+            .integer_div(InterpreterType::Integer(i32::MAX))
+            .unwrap();
+        assert!(matches!(quotient, InterpreterType::Integer(i32::MAX)));
+    }
+
+    #[test]
+    fn test_integer_div_truncates_the_quotient() {
+        let div = |left: InterpreterType, right: InterpreterType| {
+            left.integer_div(right).map(|value| value.to_string())
+        };
+        let (int, real) = (InterpreterType::Integer, InterpreterType::Real);
+        assert_eq!(div(int(7), int(2)), Ok("3".to_string()));
+        assert_eq!(div(real(7.5), int(2)), Ok("3".to_string()));
+        assert_eq!(div(int(7), real(2.5)), Ok("2".to_string()));
+        assert_eq!(div(real(-7.5), real(0.5)), Ok("-15".to_string()));
+        assert_eq!(div(int(-7), int(2)), Ok("-3".to_string()));
+        assert_eq!(div(int(7), int(0)), Err("Division by zero".to_string()));
+        assert_eq!(
+            div(real(7.5), real(0.0)),
+            Err("Division by zero".to_string())
+        );
+        assert_eq!(
+            div(int(i32::MIN), int(-1)),
+            Err("Integer overflow".to_string())
+        );
+        assert_eq!(div(real(1e20), int(1)), Err("Integer overflow".to_string()));
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        let (int, real) = (InterpreterType::Integer, InterpreterType::Real);
+        let check = |value: Result<InterpreterType, String>| value.unwrap().to_string();
+        assert_eq!(check(int(12).and(int(10))), "8");
+        assert_eq!(check(int(12).or(int(10))), "14");
+        assert_eq!(check(int(12).xor(int(10))), "6");
+        assert_eq!(check(int(0).invert()), "-1");
+        assert_eq!(check(int(1).shift_left(int(4))), "16");
+        assert_eq!(check(int(-16).shift_right(int(2))), "-4");
+        let yes = InterpreterType::Boolean(true);
+        assert_eq!(check(yes.clone().xor(yes.clone())), "FALSE");
+        assert_eq!(
+            real(2.5).and(int(1)).unwrap_err(),
+            "Cannot apply AND to 2.5 and 1"
+        );
+        assert_eq!(real(2.5).invert().unwrap_err(), "Cannot apply NOT to 2.5");
+        assert!(yes.or(int(1)).is_err());
+        assert_eq!(
+            int(1).shift_left(int(32)).unwrap_err(),
+            "Shift amount out of range: 32"
+        );
+        assert!(int(1).shift_right(int(-1)).is_err());
+    }
+
+    #[test]
+    fn test_modulo() {
+        let modulo =
+            |left, right| InterpreterType::Integer(left).modulo(InterpreterType::Integer(right));
+        assert_eq!(modulo(5, 3).unwrap().to_string(), "2");
+        assert_eq!(modulo(-7, 2).unwrap().to_string(), "-1");
+        let big = InterpreterType::Integer(i32::MAX).widen();
+        let product = big.clone().checked_mul(big).unwrap();
+        assert_eq!(
+            product
+                .modulo(InterpreterType::Integer(10))
+                .unwrap()
+                .to_string(),
+            "9"
+        );
+        assert!(InterpreterType::Real(5.0)
+            .modulo(InterpreterType::Integer(3))
+            .is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let compare = |left: InterpreterType, right: InterpreterType| left.compare(&right);
+        let (three, half) = (InterpreterType::Integer(3), InterpreterType::Real(3.5));
+        assert_eq!(compare(three.clone(), half.clone()), Ok(Ordering::Less));
+        assert_eq!(compare(half, three.clone()), Ok(Ordering::Greater));
+        assert_eq!(
+            compare(InterpreterType::Real(3.0), three.clone()),
+            Ok(Ordering::Equal)
+        );
+        let big = InterpreterType::Integer(i32::MAX).widen();
+        let product = big.clone().checked_mul(big).unwrap();
+        assert_eq!(compare(product, three.clone()), Ok(Ordering::Greater));
+        assert_eq!(
+            compare(InterpreterType::Boolean(true), three.clone()),
+            Err("Cannot compare TRUE and 3".to_string())
+        );
+        assert!(compare(InterpreterType::Real(f64::NAN), three).is_err());
+    }
+
+    #[test]
+    fn test_booleans_are_not_numbers() {
+        let (yes, one) = (InterpreterType::Boolean(true), InterpreterType::Integer(1));
+        assert_eq!(
+            yes.clone().checked_add(one.clone()).unwrap_err(),
+            "Expected a number, found TRUE"
+        );
+        assert!(yes.clone().checked_neg().is_err());
+        assert!(one.clone().pow(yes.clone()).is_err());
+        assert!(one.clone().integer_div(yes.clone()).is_err());
+        assert_eq!(yes.is_truthy(), Ok(true));
+        assert_eq!(InterpreterType::Boolean(false).is_truthy(), Ok(false));
+        assert_eq!(
+            one.is_truthy().unwrap_err(),
+            "Condition must be a boolean, found 1"
+        );
+        assert_eq!(yes.as_bool(), Ok(true));
+        assert_eq!(one.as_bool().unwrap_err(), "Expected a boolean, found 1");
+    }
+
+    #[test]
+    fn test_as_index() {
+        assert_eq!(InterpreterType::Integer(2).as_index(), Ok(2));
+        assert_eq!(InterpreterType::Real(2.0).as_index(), Ok(2));
+        assert_eq!(
+            InterpreterType::Real(2.5).as_index(),
+            Err("Invalid index: 2.5".to_string())
+        );
+        assert!(InterpreterType::Real(f64::NAN).as_index().is_err());
+        let big = InterpreterType::Integer(i32::MAX).widen();
+        assert!(big.clone().checked_mul(big).unwrap().as_index().is_err());
+    }
 }
@@ -1,5 +1,7 @@
-use std::{error::Error, fmt};
+use std::{cmp::Ordering, error::Error, fmt, rc::Rc};
 
+mod bigint;
+mod builtins;
 mod kind;
 
 use crate::{
@@ -7,11 +9,46 @@ use crate::{
     parser::Parser,
     token::{Token, TokenKind},
 };
-use kind::InterpreterType;
+pub use bigint::BigInt;
+pub use kind::{InterpreterType, NumericOps};
+
+pub const DEFAULT_MAX_DEPTH: usize = 1_000;
+// Each procedure call nests several visits, so calls get a lower limit of
+// their own that still fits in the stack of a main thread.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 256;
+
+// Host functions receive the evaluated arguments and validate their own arity.
+pub type NativeFunction = Box<dyn Fn(&[InterpreterType]) -> Result<InterpreterType, String>>;
+
+// Parameter names and types and body of a declared procedure. The body is
+// shared so a call doesn't have to copy it.
+type Procedure = (Vec<(String, TokenKind)>, Rc<AstNode>);
 
 pub struct Interpreter<'a> {
-    pub parser: &'a mut Parser<'a>,
+    pub parser: Option<&'a mut Parser<'a>>,
     pub global_scope: std::collections::HashMap<String, InterpreterType>,
+    // Local scopes of the active procedure calls, innermost last
+    scopes: Vec<std::collections::HashMap<String, InterpreterType>>,
+    // Nested visits within the running procedure body or main program
+    pub max_depth: usize,
+    // Procedure calls that haven't returned yet
+    pub max_call_depth: usize,
+    pub case_sensitive: bool,
+    pub bignum: bool,
+    pub lenient: bool,
+    // Record each visited node and its result in `trace_log`
+    pub trace: bool,
+    trace_log: Vec<String>,
+    functions: std::collections::HashMap<String, NativeFunction>,
+    procedures: std::collections::HashMap<String, Procedure>,
+    constants: std::collections::HashSet<String>,
+    types: std::collections::HashMap<String, TokenKind>,
+    // Text printed by WRITE and WRITELN
+    output: String,
+    depth: usize,
+    // The visit depth at which the running procedure body started
+    call_base: usize,
+    call_depth: usize,
 }
 
 #[derive(Debug)]
@@ -27,60 +64,299 @@ impl fmt::Display for InterpreterError {
 
 impl Error for InterpreterError {}
 
+impl Default for Interpreter<'_> {
+    fn default() -> Self {
+        Interpreter {
+            parser: None,
+            global_scope: std::collections::HashMap::new(),
+            scopes: vec![],
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            case_sensitive: false,
+            bignum: false,
+            lenient: false,
+            trace: false,
+            trace_log: vec![],
+            functions: std::collections::HashMap::new(),
+            procedures: std::collections::HashMap::new(),
+            constants: std::collections::HashSet::new(),
+            types: std::collections::HashMap::new(),
+            output: String::new(),
+            depth: 0,
+            call_base: 0,
+            call_depth: 0,
+        }
+    }
+}
+
+// A short description of a node for the trace log, like `BinaryOp(+)`.
+fn trace_label(node: &AstNode) -> String {
+    match node {
+        AstNode::BinaryOp(_, _, token) => format!("BinaryOp({})", token.lexeme),
+        AstNode::UnaryOp(_, token) => format!("UnaryOp({})", token.lexeme),
+        AstNode::Num(_) => format!("Num({})", node),
+        AstNode::Var(token) => format!("Var({})", token.value),
+        AstNode::Assign(left, _, _) => format!("Assign({})", left),
+        AstNode::Compound(_) => "Compound".to_string(),
+        AstNode::NoOp => "NoOp".to_string(),
+        AstNode::Program(name, _) => format!("Program({})", name),
+        AstNode::Block(..) => "Block".to_string(),
+        AstNode::VarDecl(var, _) => format!("VarDecl({})", var),
+        AstNode::ArrayDecl(var, ..) => format!("ArrayDecl({})", var),
+        AstNode::Index(token, _) => format!("Index({})", token.value),
+        AstNode::ConstDecl(name, _) => format!("ConstDecl({})", name),
+        AstNode::Type(token) => format!("Type({})", token.lexeme),
+        AstNode::FunctionCall(name, _) => format!("FunctionCall({})", name),
+        AstNode::Formatted(..) => "Formatted".to_string(),
+        AstNode::If(..) => "If".to_string(),
+        AstNode::IfExpr(..) => "IfExpr".to_string(),
+        AstNode::While(..) => "While".to_string(),
+        AstNode::Repeat(..) => "Repeat".to_string(),
+        AstNode::For(variable, ..) => format!("For({})", variable),
+        AstNode::ProcedureDecl(name, ..) => format!("ProcedureDecl({})", name),
+        AstNode::ProcedureCall(name, _) => format!("ProcedureCall({})", name),
+    }
+}
+
+// Arrays are limited so that a typo in the bounds can't exhaust memory.
+const MAX_ARRAY_LEN: i64 = 1 << 20;
+
+// The same goes for the width and decimals of a written value.
+pub const MAX_FIELD_WIDTH: i32 = 1 << 10;
+
+// Declared variables start out as zero of their type.
+fn zero(kind: &TokenKind) -> InterpreterType {
+    match kind {
+        TokenKind::Real => InterpreterType::Real(0.0),
+        TokenKind::Boolean => InterpreterType::Boolean(false),
+        TokenKind::Char => InterpreterType::Char('\0'),
+        _ => InterpreterType::Integer(0),
+    }
+}
+
+// The type of an array element, which keeps the type it was declared with.
+fn element_kind(element: &InterpreterType) -> TokenKind {
+    match element {
+        InterpreterType::Real(_) => TokenKind::Real,
+        InterpreterType::Boolean(_) => TokenKind::Boolean,
+        InterpreterType::Char(_) => TokenKind::Char,
+        _ => TokenKind::Integer,
+    }
+}
+
+// Turns an index into a position in `elements`, checking the bounds.
+fn position(
+    name: &str,
+    low: i32,
+    elements: &[InterpreterType],
+    index: &InterpreterType,
+) -> Result<usize, String> {
+    let index = index.as_index()?;
+    let high = low as i64 + elements.len() as i64 - 1;
+    if index < low as i64 || index > high {
+        return Err(format!(
+            "Index {} out of bounds for {}[{}..{}]",
+            index, name, low, high
+        ));
+    }
+    Ok((index - low as i64) as usize)
+}
+
+// Makes a value fit the declared type of the variable it is assigned to.
+// Integers widen to reals, but a real is never silently truncated.
+fn convert(
+    value: InterpreterType,
+    kind: &TokenKind,
+    name: &str,
+) -> Result<InterpreterType, String> {
+    match (kind, value) {
+        (TokenKind::Real, InterpreterType::Integer(value)) => {
+            Ok(InterpreterType::Real(value as f64))
+        }
+        (TokenKind::Real, InterpreterType::BigInt(value)) => {
+            Ok(InterpreterType::Real(value.to_f64()))
+        }
+        (TokenKind::Real, value @ InterpreterType::Real(_))
+        | (
+            TokenKind::Integer,
+            value @ (InterpreterType::Integer(_) | InterpreterType::BigInt(_)),
+        )
+        | (TokenKind::Boolean, value @ InterpreterType::Boolean(_))
+        | (TokenKind::Char, value @ InterpreterType::Char(_))
+        | (TokenKind::Array, value @ InterpreterType::Array(..)) => Ok(value),
+        // A one-character string literal fits a CHAR variable
+        (TokenKind::Char, InterpreterType::Str(value)) if value.chars().count() == 1 => {
+            Ok(InterpreterType::Char(value.chars().next().unwrap()))
+        }
+        (kind, value) => Err(format!(
+            "Cannot assign {} to {} variable {}",
+            value,
+            kind.to_string().to_uppercase(),
+            name
+        )),
+    }
+}
+
 impl<'a> Interpreter<'a> {
     pub fn new(parser: &'a mut Parser<'a>) -> Interpreter<'a> {
         Interpreter {
-            parser,
-            global_scope: std::collections::HashMap::new(),
+            parser: Some(parser),
+            ..Default::default()
         }
     }
 
+    // Forgets every global variable and constant; procedures stay defined.
+    pub fn clear_globals(&mut self) {
+        self.global_scope.clear();
+        self.constants.clear();
+        self.types.clear();
+    }
+
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    // Hands over everything printed so far, leaving the buffer empty.
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
     pub fn interpret(&mut self) -> Result<f64, String> {
-        let tree = self.parser.parse();
+        self.interpret_typed().map(|value| value.from::<f64>())
+    }
+
+    pub fn interpret_typed(&mut self) -> Result<InterpreterType, String> {
+        let tree = match self.parser.as_mut() {
+            Some(parser) => parser.parse(),
+            None => return Err("No parser to interpret".to_string()),
+        };
         match tree {
-            Ok(tree) => match self.visit(tree) {
-                Ok(value) => match value {
-                    InterpreterType::Integer(value) => Ok(value as f64),
-                    InterpreterType::Real(value) => Ok(value),
-                },
-                Err(e) => Err(e),
-            },
+            Ok(tree) => self.visit(&tree),
             Err(e) => Err(e.to_string()),
         }
     }
 
+    // Runs `;`-separated top-level statements such as `3+4; 5*6` and returns
+    // the value of each one.
+    pub fn interpret_all(&mut self) -> Result<Vec<InterpreterType>, String> {
+        let statements = match self.parser.as_mut() {
+            Some(parser) => parser.parse_statements().map_err(|e| e.to_string())?,
+            None => return Err("No parser to interpret".to_string()),
+        };
+        statements
+            .iter()
+            .map(|statement| self.visit(statement))
+            .collect()
+    }
+
     fn visit_binary_op(
         &mut self,
-        left: AstNode,
-        right: AstNode,
-        token: Token,
+        left: &AstNode,
+        right: &AstNode,
+        token: &Token,
+    ) -> Result<InterpreterType, String> {
+        // A run like `1 + 2 + 3` leans left and is as deep as it is long, so
+        // walk down to its first operand and apply the operators on the way
+        // back up instead of visiting each one. A trace still shows them all.
+        let mut operations = vec![(right, token)];
+        let mut first = left;
+        while let (AstNode::BinaryOp(left, right, token), false) = (first, self.trace) {
+            operations.push((right, token));
+            first = left;
+        }
+        let mut value = self.visit(first)?;
+        for (right, token) in operations.into_iter().rev() {
+            value = self.apply_binary_op(value, right, token)?;
+        }
+        Ok(value)
+    }
+
+    fn apply_binary_op(
+        &mut self,
+        left: InterpreterType,
+        right: &AstNode,
+        token: &Token,
     ) -> Result<InterpreterType, String> {
-        let left = self.visit(left)?;
+        // On booleans AND and OR skip the right operand once the left one
+        // decides the result
+        match (&token.kind, &left) {
+            (TokenKind::And, InterpreterType::Boolean(false))
+            | (TokenKind::Or, InterpreterType::Boolean(true)) => return Ok(left),
+            _ => {}
+        }
         let right = self.visit(right)?;
+        // Bitwise operators work on plain integers, so they run before the
+        // bignum widening below
+        match token.kind {
+            TokenKind::And => return left.and(right),
+            TokenKind::Or => return left.or(right),
+            TokenKind::Xor => return left.xor(right),
+            TokenKind::Shl => return left.shift_left(right),
+            TokenKind::Shr => return left.shift_right(right),
+            _ => {}
+        }
+        let (left, right) = (self.numeric(left), self.numeric(right));
+        let divides = [
+            TokenKind::FloatDivide,
+            TokenKind::IntegerDivide,
+            TokenKind::Modulo,
+        ]
+        .contains(&token.kind);
+        // Zero to a negative power divides by zero too
+        let inverts_zero = token.kind == TokenKind::Power
+            && left.is_zero()
+            && right
+                .compare(&InterpreterType::Integer(0))
+                .is_ok_and(|order| order.is_lt());
+        if (divides && right.is_zero()) || inverts_zero {
+            return Err(format!(
+                "Division by zero at line {}, column {}",
+                token.line, token.column
+            ));
+        }
         match token.kind {
-            TokenKind::Plus => Ok(left + right),
-            TokenKind::Minus => Ok(left - right),
-            TokenKind::Multiply => Ok(left * right),
-            TokenKind::FloatDivide => Ok(left / right),
-            TokenKind::IntegerDivide => Ok(left.integer_div(right)),
+            TokenKind::Plus => left.checked_add(right),
+            TokenKind::Minus => left.checked_sub(right),
+            TokenKind::Multiply => left.checked_mul(right),
+            TokenKind::FloatDivide => left.checked_div(right),
+            TokenKind::IntegerDivide => left.integer_div(right),
+            TokenKind::Modulo => left.modulo(right),
+            TokenKind::Power => left.pow(right),
+            TokenKind::Equal => Ok(InterpreterType::Boolean(left.compare(&right)?.is_eq())),
+            TokenKind::NotEqual => Ok(InterpreterType::Boolean(left.compare(&right)?.is_ne())),
+            TokenKind::LessThan => Ok(InterpreterType::Boolean(left.compare(&right)?.is_lt())),
+            TokenKind::LessEqual => Ok(InterpreterType::Boolean(left.compare(&right)?.is_le())),
+            TokenKind::GreaterThan => Ok(InterpreterType::Boolean(left.compare(&right)?.is_gt())),
+            TokenKind::GreaterEqual => Ok(InterpreterType::Boolean(left.compare(&right)?.is_ge())),
             _ => Err("Invalid token".to_string()),
         }
     }
 
+    fn numeric(&self, value: InterpreterType) -> InterpreterType {
+        // In bignum mode integer arithmetic runs on BigInt so it can't overflow
+        if self.bignum {
+            value.widen()
+        } else {
+            value
+        }
+    }
+
     fn visit_num(&mut self, num: InterpreterType) -> Result<InterpreterType, String> {
         Ok(num)
     }
 
-    fn visit_unary_op(&mut self, node: AstNode, token: Token) -> Result<InterpreterType, String> {
+    fn visit_unary_op(&mut self, node: &AstNode, token: &Token) -> Result<InterpreterType, String> {
         let node = self.visit(node)?;
+        let node = self.numeric(node);
         match token.kind {
             TokenKind::Plus => Ok(node),
-            TokenKind::Minus => Ok(-node),
+            TokenKind::Minus => node.checked_neg(),
+            TokenKind::Not => node.invert(),
             _ => Err("Invalid token".to_string()),
         }
     }
 
-    fn visit_compound(&mut self, nodes: Vec<AstNode>) -> Result<InterpreterType, String> {
+    fn visit_compound(&mut self, nodes: &[AstNode]) -> Result<InterpreterType, String> {
         for node in nodes {
             self.visit(node)?;
         }
@@ -89,62 +365,573 @@ impl<'a> Interpreter<'a> {
 
     fn visit_assignment(
         &mut self,
-        left: AstNode,
-        right: AstNode,
-        _token: Token,
+        left: &AstNode,
+        right: &AstNode,
+        _token: &Token,
     ) -> Result<InterpreterType, String> {
         let string = match left {
-            AstNode::Var(token) => token.value,
+            AstNode::Var(token) => &token.value,
+            AstNode::Index(token, index) => {
+                let name = self.scope_key(token.value.parse::<String>());
+                let index = self.visit(index)?;
+                let value = self.visit(right)?;
+                self.assign_element(name, index, value.clone())?;
+                return Ok(value);
+            }
             _ => return Err("Invalid token".to_string()),
         };
-        let string = string.parse::<String>();
+        let string = self.scope_key(string.parse::<String>());
         let value = self.visit(right)?;
-        self.global_scope.insert(string, value);
+        self.assign(string, value.clone())?;
         Ok(value)
     }
 
-    fn visit_var(&mut self, token: Token) -> Result<InterpreterType, String> {
-        let string = token.value.parse::<String>();
-        match self.global_scope.get(&string) {
-            Some(value) => Ok(*value),
+    fn visit_var(&mut self, token: &Token) -> Result<InterpreterType, String> {
+        let string = self.scope_key(token.value.parse::<String>());
+        if let Some(value) = self.lookup(&string) {
+            return Ok(value.clone());
+        }
+        match builtins::constant(&string) {
+            Some(value) => Ok(value),
+            None if self.lenient => Ok(InterpreterType::Integer(0)),
+            None => Err("Variable not found".to_string()),
+        }
+    }
+
+    fn visit_index(&mut self, token: &Token, index: &AstNode) -> Result<InterpreterType, String> {
+        let name = self.scope_key(token.value.parse::<String>());
+        let index = self.visit(index)?;
+        match self.lookup(&name) {
+            Some(InterpreterType::Array(low, elements)) => {
+                Ok(elements[position(&name, *low, elements, &index)?].clone())
+            }
+            Some(value) => Err(format!("{} is not an array: {}", name, value)),
+            None => Err("Variable not found".to_string()),
+        }
+    }
+
+    fn visit_function_call(
+        &mut self,
+        name: &str,
+        args: &[AstNode],
+    ) -> Result<InterpreterType, String> {
+        // A bare `writeln(x)` typed at the prompt parses as a function call
+        let key = self.scope_key(name.to_string());
+        if !self.functions.contains_key(&key) && (key == "write" || key == "writeln") {
+            return self.visit_write(args, key == "writeln");
+        }
+        // Builds the text WRITE would print and returns it instead
+        if !self.functions.contains_key(&key) && key == "format" {
+            return self.write_text(args).map(InterpreterType::Str);
+        }
+        let mut values = vec![];
+        for arg in args {
+            values.push(self.visit(arg)?);
+        }
+        match self.functions.get(&key) {
+            Some(function) => function(&values),
+            None => builtins::call(&key, &values),
+        }
+    }
+
+    fn visit_procedure_decl(
+        &mut self,
+        name: &str,
+        params: &[AstNode],
+        block: &AstNode,
+    ) -> Result<InterpreterType, String> {
+        let mut names = vec![];
+        for param in params {
+            match param {
+                AstNode::VarDecl(var, type_node) => match (&**var, &**type_node) {
+                    (AstNode::Var(token), AstNode::Type(kind)) => names.push((
+                        self.scope_key(token.value.parse::<String>()),
+                        kind.kind.clone(),
+                    )),
+                    _ => return Err("Invalid token".to_string()),
+                },
+                AstNode::ArrayDecl(..) => {
+                    return Err("Array parameters are not supported".to_string())
+                }
+                _ => return Err("Invalid token".to_string()),
+            }
+        }
+        let name = self.scope_key(name.to_string());
+        self.procedures
+            .insert(name, (names, Rc::new(block.clone())));
+        Ok(InterpreterType::Real(0.0))
+    }
+
+    fn visit_procedure_call(
+        &mut self,
+        name: &str,
+        args: &[AstNode],
+    ) -> Result<InterpreterType, String> {
+        let key = self.scope_key(name.to_string());
+        let (params, block) = match self.procedures.get(&key) {
+            Some(procedure) => procedure.clone(),
+            None if key == "write" || key == "writeln" => {
+                return self.visit_write(args, key == "writeln")
+            }
+            None => return Err(format!("Unknown procedure: {}", name)),
+        };
+        if params.len() != args.len() {
+            return Err(format!(
+                "{} expects {} argument(s), got {}",
+                name,
+                params.len(),
+                args.len()
+            ));
+        }
+        // Arguments convert to the declared types as if assigned
+        let mut values = vec![];
+        for (arg, (param, kind)) in args.iter().zip(&params) {
+            let value = self.visit(arg)?;
+            values.push((param.clone(), convert(value, kind, param)?));
+        }
+        if self.call_depth >= self.max_call_depth {
+            return Err("Maximum call depth exceeded".to_string());
+        }
+        // Locals and parameters may hide declared globals, so their types
+        // and constness only apply until the call returns
+        let types = self.types.clone();
+        let constants = self.constants.clone();
+        for (param, kind) in params {
+            self.constants.remove(&param);
+            self.types.insert(param, kind);
+        }
+        // Each call gets `max_depth` visits of its own, so the number of
+        // calls is bounded by `max_call_depth` alone
+        let call_base = std::mem::replace(&mut self.call_base, self.depth);
+        self.call_depth += 1;
+        self.scopes.push(values.into_iter().collect());
+        let result = self.visit(&block);
+        self.call_depth -= 1;
+        self.call_base = call_base;
+        self.scopes.pop();
+        self.types = types;
+        self.constants = constants;
+        result.map(|_| InterpreterType::Real(0.0))
+    }
+
+    fn visit_write(&mut self, args: &[AstNode], newline: bool) -> Result<InterpreterType, String> {
+        let text = self.write_text(args)?;
+        self.output.push_str(&text);
+        if newline {
+            self.output.push('\n');
+        }
+        Ok(InterpreterType::Real(0.0))
+    }
+
+    fn write_text(&mut self, args: &[AstNode]) -> Result<String, String> {
+        let mut text = String::new();
+        for arg in args {
+            match arg {
+                AstNode::Formatted(value, width, precision) => {
+                    text.push_str(&self.visit_formatted(value, width, precision)?)
+                }
+                arg => text.push_str(&self.visit(arg)?.to_string()),
+            }
+        }
+        Ok(text)
+    }
+
+    // Pascal's `x:width:decimals`: the value is right-aligned in a field of
+    // at least `width` characters, and a number given decimals is written
+    // in fixed point with that many.
+    fn visit_formatted(
+        &mut self,
+        value: &AstNode,
+        width: &AstNode,
+        precision: &AstNode,
+    ) -> Result<String, String> {
+        let value = self.visit(value)?;
+        let width = self.field_size(width)?;
+        let text = match precision {
+            AstNode::NoOp => value.to_string(),
+            precision => {
+                let decimals = self.field_size(precision)?;
+                if !value.is_number() {
+                    return Err(format!("Decimals need a number, found {}", value));
+                }
+                format!("{:.*}", decimals, f64::from(value))
+            }
+        };
+        Ok(format!("{:>1$}", text, width))
+    }
+
+    fn field_size(&mut self, node: &AstNode) -> Result<usize, String> {
+        match self.visit(node)? {
+            InterpreterType::Integer(size) if (0..=MAX_FIELD_WIDTH).contains(&size) => {
+                Ok(size as usize)
+            }
+            size => Err(format!("Invalid field width: {}", size)),
+        }
+    }
+
+    pub fn register_function<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[InterpreterType]) -> Result<InterpreterType, String> + 'static,
+    {
+        let name = self.scope_key(name.to_string());
+        self.functions.insert(name, Box::new(function));
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: InterpreterType) {
+        let name = self.scope_key(name.to_string());
+        self.global_scope.insert(name, value);
+    }
+
+    fn lookup(&self, name: &str) -> Option<&InterpreterType> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .or_else(|| self.global_scope.get(name))
+    }
+
+    // Updates the nearest existing binding, or creates one in the current scope.
+    fn assign(&mut self, name: String, value: InterpreterType) -> Result<(), String> {
+        if self.constants.contains(&name) {
+            return Err(format!("Cannot assign to constant {}", name));
+        }
+        let value = match self.types.get(&name) {
+            Some(kind) => convert(value, kind, &name)?,
+            None => value,
+        };
+        match self.binding_mut(&name) {
+            Some(binding) => {
+                // Whole arrays can only replace arrays with the same bounds
+                // and element type
+                if let (InterpreterType::Array(low, old), InterpreterType::Array(new_low, new)) =
+                    (&*binding, &value)
+                {
+                    if low != new_low || old.len() != new.len() {
+                        return Err(format!(
+                            "Cannot assign {} to {}: the bounds differ",
+                            value, name
+                        ));
+                    }
+                    if element_kind(&old[0]) != element_kind(&new[0]) {
+                        return Err(format!(
+                            "Cannot assign {} to {}: the element types differ",
+                            value, name
+                        ));
+                    }
+                }
+                *binding = value;
+            }
+            None => self.declare(name, value),
+        }
+        Ok(())
+    }
+
+    fn assign_element(
+        &mut self,
+        name: String,
+        index: InterpreterType,
+        value: InterpreterType,
+    ) -> Result<(), String> {
+        if self.constants.contains(&name) {
+            return Err(format!("Cannot assign to constant {}", name));
+        }
+        match self.binding_mut(&name) {
+            Some(InterpreterType::Array(low, elements)) => {
+                let position = position(&name, *low, elements, &index)?;
+                let element = &mut elements[position];
+                *element = convert(value, &element_kind(element), &name)?;
+                Ok(())
+            }
+            Some(value) => Err(format!("{} is not an array: {}", name, value)),
             None => Err("Variable not found".to_string()),
         }
     }
 
-    fn visit_program(&mut self, _name: String, block: AstNode) -> Result<InterpreterType, String> {
+    // The nearest binding of `name`, searching the innermost scope first.
+    fn binding_mut(&mut self, name: &str) -> Option<&mut InterpreterType> {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(binding) = scope.get_mut(name) {
+                return Some(binding);
+            }
+        }
+        self.global_scope.get_mut(name)
+    }
+
+    fn declare(&mut self, name: String, value: InterpreterType) {
+        match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name, value),
+            None => self.global_scope.insert(name, value),
+        };
+    }
+
+    fn visit_var_decl(
+        &mut self,
+        var: &AstNode,
+        type_node: &AstNode,
+    ) -> Result<InterpreterType, String> {
+        let name = match var {
+            AstNode::Var(token) => self.scope_key(token.value.parse::<String>()),
+            _ => return Err("Invalid token".to_string()),
+        };
+        let kind = match type_node {
+            AstNode::Type(token) => token.kind.clone(),
+            _ => return Err("Invalid token".to_string()),
+        };
+        let value = zero(&kind);
+        self.types.insert(name.clone(), kind);
+        self.constants.remove(&name);
+        self.declare(name, value);
+        Ok(InterpreterType::Real(0.0))
+    }
+
+    fn visit_array_decl(
+        &mut self,
+        var: &AstNode,
+        low: i32,
+        high: i32,
+        type_node: &AstNode,
+    ) -> Result<InterpreterType, String> {
+        let name = match var {
+            AstNode::Var(token) => self.scope_key(token.value.parse::<String>()),
+            _ => return Err("Invalid token".to_string()),
+        };
+        let kind = match type_node {
+            AstNode::Type(token) => token.kind.clone(),
+            _ => return Err("Invalid token".to_string()),
+        };
+        let len = high as i64 - low as i64 + 1;
+        if len > MAX_ARRAY_LEN {
+            return Err(format!("Array {} is too large: {} elements", name, len));
+        }
+        let elements = vec![zero(&kind); len as usize];
+        self.types.insert(name.clone(), TokenKind::Array);
+        self.constants.remove(&name);
+        self.declare(name, InterpreterType::Array(low, elements));
+        Ok(InterpreterType::Real(0.0))
+    }
+
+    fn visit_const_decl(&mut self, name: &str, value: &AstNode) -> Result<InterpreterType, String> {
+        let name = self.scope_key(name.to_string());
+        let value = self.visit(value)?;
+        self.declare(name.clone(), value);
+        self.constants.insert(name);
+        Ok(InterpreterType::Real(0.0))
+    }
+
+    fn scope_key(&self, name: String) -> String {
+        // Pascal identifiers are case-insensitive unless strict mode is on
+        if self.case_sensitive {
+            name
+        } else {
+            name.to_lowercase()
+        }
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: &AstNode,
+        then_branch: &AstNode,
+        else_branch: &AstNode,
+    ) -> Result<InterpreterType, String> {
+        if self.visit(condition)?.is_truthy()? {
+            self.visit(then_branch)
+        } else {
+            self.visit(else_branch)
+        }
+    }
+
+    fn visit_if_expr(
+        &mut self,
+        condition: &AstNode,
+        then_expr: &AstNode,
+        else_expr: &AstNode,
+    ) -> Result<InterpreterType, String> {
+        if self.visit(condition)?.is_truthy()? {
+            self.visit(then_expr)
+        } else {
+            self.visit(else_expr)
+        }
+    }
+
+    fn visit_while(
+        &mut self,
+        condition: &AstNode,
+        body: &AstNode,
+    ) -> Result<InterpreterType, String> {
+        while self.visit(condition)?.is_truthy()? {
+            self.visit(body)?;
+        }
+        Ok(InterpreterType::Real(0.0))
+    }
+
+    fn visit_repeat(
+        &mut self,
+        body: &[AstNode],
+        condition: &AstNode,
+    ) -> Result<InterpreterType, String> {
+        // The body always runs once before the condition is checked
+        loop {
+            for node in body {
+                self.visit(node)?;
+            }
+            if self.visit(condition)?.is_truthy()? {
+                break;
+            }
+        }
+        Ok(InterpreterType::Real(0.0))
+    }
+
+    fn visit_for(
+        &mut self,
+        variable: &AstNode,
+        start: &AstNode,
+        end: &AstNode,
+        direction: &TokenKind,
+        body: &AstNode,
+    ) -> Result<InterpreterType, String> {
+        let name = match variable {
+            AstNode::Var(token) => self.scope_key(token.value.parse::<String>()),
+            _ => return Err("Invalid token".to_string()),
+        };
+        let (start, end) = (self.visit(start)?, self.visit(end)?);
+        for bound in [&start, &end] {
+            if matches!(
+                bound,
+                InterpreterType::Real(_) | InterpreterType::Boolean(_)
+            ) {
+                return Err(format!("FOR bounds must be integers, found {}", bound));
+            }
+        }
+        let (step, past_end) = match direction {
+            TokenKind::Downto => (-1, Ordering::Less),
+            _ => (1, Ordering::Greater),
+        };
+        let mut value = start;
+        if value.compare(&end)? == past_end {
+            return Ok(InterpreterType::Real(0.0));
+        }
+        loop {
+            self.assign(name.clone(), value.clone())?;
+            self.visit(body)?;
+            // Stop on the last value rather than stepping past it, so a loop
+            // ending at the integer limits can't overflow
+            if value.compare(&end)?.is_eq() {
+                break;
+            }
+            value = value.checked_add(InterpreterType::Integer(step))?;
+        }
+        Ok(InterpreterType::Real(0.0))
+    }
+
+    fn visit_program(&mut self, _name: &str, block: &AstNode) -> Result<InterpreterType, String> {
         self.visit(block)
     }
 
     fn visit_block(
         &mut self,
-        declarations: Vec<AstNode>,
-        compound_statement: AstNode,
+        declarations: &[AstNode],
+        compound_statement: &AstNode,
     ) -> Result<InterpreterType, String> {
+        // Each block is its own scope, so only names repeated within these
+        // declarations clash; a procedure may still shadow a global
+        let mut declared = std::collections::HashSet::new();
+        for declaration in declarations {
+            let name = match declaration {
+                AstNode::VarDecl(var, _) | AstNode::ArrayDecl(var, ..) => match &**var {
+                    AstNode::Var(token) => token.value.parse::<String>(),
+                    _ => continue,
+                },
+                AstNode::ConstDecl(name, _) | AstNode::ProcedureDecl(name, ..) => name.clone(),
+                _ => continue,
+            };
+            if !declared.insert(self.scope_key(name.clone())) {
+                return Err(format!("Duplicate declaration: {}", name));
+            }
+        }
         for declaration in declarations {
             self.visit(declaration)?;
         }
         self.visit(compound_statement)
     }
 
-    pub fn visit(&mut self, node: AstNode) -> Result<InterpreterType, String> {
+    // Evaluates a tree without consuming it, so the same tree can be run
+    // again.
+    pub fn visit(&mut self, node: &AstNode) -> Result<InterpreterType, String> {
+        if self.depth - self.call_base >= self.max_depth {
+            return Err("Maximum recursion depth exceeded".to_string());
+        }
+        if !self.trace {
+            self.depth += 1;
+            let result = self.visit_node(node);
+            self.depth -= 1;
+            return result;
+        }
+        // Nested visits are indented under the node that started them
+        let line = format!("{}visit {}", "  ".repeat(self.depth), trace_label(node));
+        self.trace_log.push(line.clone());
+        self.depth += 1;
+        let result = self.visit_node(node);
+        self.depth -= 1;
+        self.trace_log.push(match &result {
+            Ok(value) => format!("{} -> {}", line, value),
+            Err(e) => format!("{} -> error: {}", line, e),
+        });
+        result
+    }
+
+    // Lines recorded while `trace` was on, in the order they happened.
+    pub fn trace_log(&self) -> &[String] {
+        &self.trace_log
+    }
+
+    fn visit_node(&mut self, node: &AstNode) -> Result<InterpreterType, String> {
         match node {
-            AstNode::Program(name, block) => self.visit_program(name, *block),
+            AstNode::Program(name, block) => self.visit_program(name, block),
             AstNode::Block(declarations, compound_statement) => {
-                self.visit_block(declarations, *compound_statement)
+                self.visit_block(declarations, compound_statement)
             }
-            AstNode::BinaryOp(left, right, token) => self.visit_binary_op(*left, *right, token),
+            AstNode::BinaryOp(left, right, token) => self.visit_binary_op(left, right, token),
             AstNode::Num(num) => {
                 let num = match num {
-                    AstType::Integer(value) => InterpreterType::Integer(value),
-                    AstType::Real(value) => InterpreterType::Real(value),
+                    AstType::Integer(value) => InterpreterType::Integer(*value),
+                    AstType::BigInt(value) => InterpreterType::BigInt(value.clone()),
+                    AstType::Real(value) => InterpreterType::Real(*value),
+                    AstType::Boolean(value) => InterpreterType::Boolean(*value),
+                    AstType::Str(value) => InterpreterType::Str(value.clone()),
+                    AstType::Char(value) => InterpreterType::Char(*value),
                     // _ => return Err("Invalid token".to_string()),
                 };
                 self.visit_num(num)
             }
-            AstNode::UnaryOp(node, token) => self.visit_unary_op(*node, token),
+            AstNode::UnaryOp(node, token) => self.visit_unary_op(node, token),
             AstNode::Compound(nodes) => self.visit_compound(nodes),
-            AstNode::Assign(left, right, token) => self.visit_assignment(*left, *right, token),
+            AstNode::Assign(left, right, token) => self.visit_assignment(left, right, token),
             AstNode::Var(token) => self.visit_var(token),
+            AstNode::FunctionCall(name, args) => self.visit_function_call(name, args),
+            AstNode::If(condition, then_branch, else_branch) => {
+                self.visit_if(condition, then_branch, else_branch)
+            }
+            AstNode::IfExpr(condition, then_expr, else_expr) => {
+                self.visit_if_expr(condition, then_expr, else_expr)
+            }
+            AstNode::While(condition, body) => self.visit_while(condition, body),
+            AstNode::Repeat(body, condition) => self.visit_repeat(body, condition),
+            AstNode::For(variable, start, end, direction, body) => {
+                self.visit_for(variable, start, end, direction, body)
+            }
+            AstNode::ProcedureDecl(name, params, block) => {
+                self.visit_procedure_decl(name, params, block)
+            }
+            AstNode::ProcedureCall(name, args) => self.visit_procedure_call(name, args),
+            AstNode::VarDecl(var, type_node) => self.visit_var_decl(var, type_node),
+            AstNode::ArrayDecl(var, low, high, type_node) => {
+                self.visit_array_decl(var, *low, *high, type_node)
+            }
+            AstNode::Index(token, index) => self.visit_index(token, index),
+            AstNode::ConstDecl(name, value) => self.visit_const_decl(name, value),
+            AstNode::Formatted(..) => {
+                Err("Field widths only apply to write, writeln and format".to_string())
+            }
             _ => Ok(InterpreterType::Real(0.0)),
         }
     }
@@ -283,6 +1070,61 @@ mod tests {
         assert_eq!(interpreter.interpret().unwrap(), 1.0)
     }
 
+    #[test]
+    fn test_interpret_typed_keeps_integer_and_real_apart() {
+        let mut lexer = Lexer::new("7 DIV 2".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert!(matches!(
+            interpreter.interpret_typed(),
+            Ok(InterpreterType::Integer(3))
+        ));
+
+        let mut lexer = Lexer::new("7 / 2".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert!(matches!(
+            interpreter.interpret_typed(),
+            Ok(InterpreterType::Real(value)) if value == 3.5
+        ));
+    }
+
+    #[test]
+    fn test_modulo() {
+        let mut lexer = Lexer::new("10 MOD 3".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 1.0)
+    }
+
+    #[test]
+    fn test_modulo_assignment() {
+        let mut lexer = Lexer::new("BEGIN a := 17 MOD 5; END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(interpreter.global_scope.get("a").unwrap().from::<i32>(), 2)
+    }
+
+    #[test]
+    fn test_modulo_with_real_is_an_error() {
+        let mut lexer = Lexer::new("7.5 MOD 2".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "MOD expects integer operands"
+        );
+    }
+
+    #[test]
+    fn test_division_next_to_line_comment() {
+        let mut lexer = Lexer::new("6 / 2 // halves".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 3.0)
+    }
+
     #[test]
     fn test_sum_and_multiplication() {
         let mut lexer = Lexer::new("3+1*2".to_string());
@@ -325,20 +1167,844 @@ mod tests {
     }
 
     #[test]
-    fn test_with_program() {
-        let string = "
-            PROGRAM Part10;
-            VAR
-               x, y, z : INTEGER;
-            BEGIN {Part10}
-               BEGIN
-                   x := 5;
-                   y := x + 10;
-                   z := y DIV 3;
-               END;
-            END.  "
-            .to_string();
-        let mut lexer = Lexer::new(string);
+    fn test_if_then_else() {
+        let mut lexer = Lexer::new("BEGIN IF TRUE THEN a := 5 ELSE a := 10; END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(interpreter.global_scope.get("a").unwrap().from::<i32>(), 5)
+    }
+
+    #[test]
+    fn test_if_takes_else_branch_on_false() {
+        let mut lexer = Lexer::new(
+            "BEGIN x := 2; IF x <> 2 THEN a := 5 ELSE BEGIN a := 10; b := 1 END; END.".to_string(),
+        );
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(interpreter.global_scope.get("a").unwrap().from::<i32>(), 10);
+        assert_eq!(interpreter.global_scope.get("b").unwrap().from::<i32>(), 1)
+    }
+
+    #[test]
+    fn test_if_without_else() {
+        let mut lexer = Lexer::new("BEGIN a := 1; IF FALSE THEN a := 5 END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(interpreter.global_scope.get("a").unwrap().from::<i32>(), 1)
+    }
+
+    #[test]
+    fn test_conditions_must_be_boolean() {
+        for input in [
+            "BEGIN IF 1 THEN a := 5 END.",
+            "BEGIN x := 2; WHILE x DO x := x - 1 END.",
+            "BEGIN REPEAT x := 0 UNTIL x END.",
+            "BEGIN a := IF 0 THEN 1 ELSE 2 END.",
+        ] {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert!(
+                interpreter
+                    .interpret()
+                    .unwrap_err()
+                    .starts_with("Condition must be a boolean, found"),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_comparisons() {
+        for (input, expected) in [("3 < 5", true), ("5 = 5", true), ("4 <> 4", false)] {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert!(matches!(
+                interpreter.interpret_typed(),
+                Ok(InterpreterType::Boolean(value)) if value == expected
+            ));
+        }
+    }
+
+    #[test]
+    fn test_long_chains_of_signs() {
+        let inputs = [
+            (format!("{}5", "-".repeat(1000)), 5),
+            (format!("{}5", "-".repeat(1001)), -5),
+            (format!("1 - {}(3 + 4)", "- + ".repeat(999)), 8),
+        ];
+        for (input, expected) in inputs {
+            let mut lexer = Lexer::new(input);
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            interpreter.max_depth = 10;
+            assert!(matches!(
+                interpreter.interpret_typed(),
+                Ok(InterpreterType::Integer(value)) if value == expected
+            ));
+        }
+    }
+
+    #[test]
+    fn test_reals_with_leading_dot() {
+        let mut lexer = Lexer::new(".5 + .5".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert!(matches!(
+            interpreter.interpret_typed(),
+            Ok(InterpreterType::Real(value)) if value == 1.0
+        ));
+
+        let mut lexer = Lexer::new("BEGIN x := -.5 END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert!(matches!(
+            interpreter.global_scope.get("x"),
+            Some(InterpreterType::Real(value)) if *value == -0.5
+        ));
+    }
+
+    #[test]
+    fn test_builtin_constants() {
+        let interpret = |source: &str| {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            interpreter.interpret()
+        };
+        assert_eq!(format!("{:.3}", interpret("2 * pi").unwrap()), "6.283");
+        assert_eq!(format!("{:.3}", interpret("pi() + E").unwrap()), "5.860");
+        assert_eq!(
+            interpret("pi(1)").unwrap_err(),
+            "pi expects 0 argument(s), got 1"
+        );
+
+        // A variable of the same name takes precedence
+        let source = "PROGRAM P; VAR pi : INTEGER; x : INTEGER; BEGIN pi := 3; x := 2 * pi END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert!(matches!(
+            interpreter.global_scope.get("x"),
+            Some(InterpreterType::Integer(6))
+        ));
+    }
+
+    #[test]
+    fn test_mixed_comparisons() {
+        let inputs = [
+            ("3 < 3.5", true),
+            ("3.0 = 3", true),
+            ("-1 >= -1.5", true),
+            ("2147483647 * 2.0 > 2147483647", true),
+            ("TRUE <> FALSE", true),
+        ];
+        for (input, expected) in inputs {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert!(
+                matches!(
+                    interpreter.interpret_typed(),
+                    Ok(InterpreterType::Boolean(value)) if value == expected
+                ),
+                "{}",
+                input
+            );
+        }
+
+        for (input, expected) in [
+            ("TRUE < 1", "Cannot compare TRUE and 1"),
+            ("0.5 = FALSE", "Cannot compare 0.5 and FALSE"),
+            ("'a' = 1", "Cannot compare 'a' and 1"),
+        ] {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert_eq!(interpreter.interpret().unwrap_err(), expected);
+        }
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        let inputs = [
+            ("TRUE AND FALSE", false),
+            ("NOT (3 < 2)", true),
+            ("FALSE OR 1 < 2 AND TRUE", true),
+            ("NOT TRUE OR NOT FALSE", true),
+        ];
+        for (input, expected) in inputs {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert!(
+                matches!(
+                    interpreter.interpret_typed(),
+                    Ok(InterpreterType::Boolean(value)) if value == expected
+                ),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        let inputs = [
+            ("12 AND 10", 8),
+            ("1 SHL 4", 16),
+            ("12 OR 3 XOR 1", 14),
+            ("NOT 0", -1),
+            ("2 + 1 SHL 3", 10),
+        ];
+        for (input, expected) in inputs {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert!(
+                matches!(
+                    interpreter.interpret_typed(),
+                    Ok(InterpreterType::Integer(value)) if value == expected
+                ),
+                "{}",
+                input
+            );
+        }
+
+        let mut lexer = Lexer::new("1.5 SHL 2".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret_typed().unwrap_err(),
+            "Cannot apply SHL to 1.5 and 2"
+        );
+    }
+
+    #[test]
+    fn test_logical_operators_short_circuit() {
+        let mut lexer = Lexer::new("FALSE AND missing OR TRUE OR missing".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert!(matches!(
+            interpreter.interpret_typed(),
+            Ok(InterpreterType::Boolean(true))
+        ));
+    }
+
+    #[test]
+    fn test_boolean_type_errors() {
+        let inputs = [
+            ("TRUE + 1", "Expected a number, found TRUE"),
+            ("1 AND TRUE", "Cannot apply AND to 1 and TRUE"),
+            ("NOT 2.5", "Cannot apply NOT to 2.5"),
+        ];
+        for (input, expected) in inputs {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert_eq!(interpreter.interpret_typed().unwrap_err(), expected);
+        }
+    }
+
+    #[test]
+    fn test_boolean_variable() {
+        let source = "PROGRAM P; VAR done : BOOLEAN; BEGIN IF NOT done THEN done := 2 > 1 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert!(matches!(
+            interpreter.global_scope.get("done"),
+            Some(InterpreterType::Boolean(true))
+        ));
+    }
+
+    #[test]
+    fn test_constants() {
+        let source = "PROGRAM P; CONST RATE = 1.5; VAR r : REAL; BEGIN r := 2 * rate END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert_eq!(
+            interpreter.global_scope.get("r").unwrap().from::<f64>(),
+            3.0
+        );
+
+        for statement in ["PI := 3", "FOR PI := 1 TO 2 DO"] {
+            let source = format!("PROGRAM P; CONST PI = 3.14; BEGIN {} END.", statement);
+            let mut lexer = Lexer::new(source);
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert_eq!(
+                interpreter.interpret().unwrap_err(),
+                "Cannot assign to constant pi"
+            );
+        }
+    }
+
+    #[test]
+    fn test_locals_shadow_constants() {
+        let source = "PROGRAM P; CONST n = 1; VAR r : INTEGER;
+            PROCEDURE Q(n : INTEGER); BEGIN n := n + 1; r := n END;
+            PROCEDURE S; VAR n : INTEGER; BEGIN n := 5; r := r + n END;
+            PROCEDURE T; CONST m = 2; BEGIN r := r + m END;
+            BEGIN Q(2); S; T; m := 1 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert!(matches!(
+            interpreter.global_scope.get("r"),
+            Some(InterpreterType::Integer(10))
+        ));
+
+        // The global is still a constant once the procedure returns
+        let source = "PROGRAM P; CONST n = 1; PROCEDURE Q(n : INTEGER); BEGIN n := 2 END; BEGIN Q(1); n := 3 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "Cannot assign to constant n"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_declaration() {
+        let inputs = [
+            "PROGRAM P; VAR x : INTEGER; x : REAL; BEGIN END.",
+            "PROGRAM P; CONST x = 1; VAR X : INTEGER; BEGIN END.",
+        ];
+        for source in inputs {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            let message = interpreter.interpret().unwrap_err();
+            assert!(
+                message.starts_with("Duplicate declaration: "),
+                "{}",
+                message
+            );
+        }
+
+        let source = "PROGRAM P; VAR x : INTEGER; BEGIN x := 1 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert!(interpreter.interpret().is_ok());
+    }
+
+    #[test]
+    fn test_empty_bodies() {
+        for source in ["PROGRAM P; BEGIN END.", "BEGIN BEGIN END; END."] {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert!(interpreter.interpret().is_ok(), "{}", source);
+            assert!(interpreter.global_scope.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_procedure_may_redeclare_a_global() {
+        let source = "PROGRAM P; VAR x : INTEGER; PROCEDURE Q; VAR x : REAL; BEGIN x := 0.5 END; BEGIN x := 1; Q END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert_eq!(interpreter.global_scope.get("x").unwrap().from::<i32>(), 1);
+    }
+
+    #[test]
+    fn test_assignment_follows_declared_type() {
+        let source = "PROGRAM P; VAR x : INTEGER; y : REAL; BEGIN y := 2.5; x := 3.7 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "Cannot assign 3.7 to INTEGER variable x"
+        );
+        assert!(matches!(
+            interpreter.global_scope.get("y"),
+            Some(InterpreterType::Real(value)) if *value == 2.5
+        ));
+        assert!(matches!(
+            interpreter.global_scope.get("x"),
+            Some(InterpreterType::Integer(0))
+        ));
+    }
+
+    #[test]
+    fn test_real_variable_widens_integers() {
+        let source = "PROGRAM P; VAR y : REAL; BEGIN y := 7 DIV 2 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert!(matches!(
+            interpreter.global_scope.get("y"),
+            Some(InterpreterType::Real(value)) if *value == 3.0
+        ));
+    }
+
+    #[test]
+    fn test_local_declaration_types_end_with_the_call() {
+        let source = "PROGRAM P; VAR x : INTEGER; PROCEDURE Q; VAR x : REAL; BEGIN x := 0.5 END; BEGIN Q; x := 2 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert!(matches!(
+            interpreter.global_scope.get("x"),
+            Some(InterpreterType::Integer(2))
+        ));
+    }
+
+    #[test]
+    fn test_string_concatenation() {
+        let mut lexer = Lexer::new("'foo' + 'bar'".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert!(matches!(
+            interpreter.interpret_typed(),
+            Ok(InterpreterType::Str(value)) if value == "foobar"
+        ));
+
+        let mut lexer = Lexer::new("'foo' + 3".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret_typed().unwrap_err(),
+            "Cannot concatenate 'foo' and 3"
+        );
+    }
+
+    #[test]
+    fn test_strings_are_not_numbers() {
+        let inputs = [
+            ("'foo' * 2", "Expected a number, found 'foo'"),
+            ("-'foo'", "Expected a number, found 'foo'"),
+            ("'a' < 1", "Cannot compare 'a' and 1"),
+        ];
+        for (input, expected) in inputs {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert_eq!(interpreter.interpret_typed().unwrap_err(), expected);
+        }
+
+        let mut lexer = Lexer::new("'apple' < 'banana'".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert!(matches!(
+            interpreter.interpret_typed(),
+            Ok(InterpreterType::Boolean(true))
+        ));
+    }
+
+    #[test]
+    fn test_writeln() {
+        let mut lexer = Lexer::new("BEGIN writeln(3 + 4); END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert_eq!(interpreter.output(), "7\n");
+    }
+
+    #[test]
+    fn test_write_and_writeln() {
+        let source = "BEGIN x := 2.5; Write('x = ', x); WRITELN; write(x > 1) END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert_eq!(interpreter.take_output(), "x = 2.5\nTRUE");
+        assert_eq!(interpreter.output(), "");
+    }
+
+    #[test]
+    fn test_field_widths() {
+        let source = "BEGIN writeln(3.14159:0:2); write('a':3, 42:4, 2:0:1); writeln(TRUE:5) END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert_eq!(interpreter.output(), "3.14\n  a  422.0 TRUE\n");
+    }
+
+    #[test]
+    fn test_format() {
+        let interpret = |source: &str| {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            interpreter.interpret_typed()
+        };
+        assert!(matches!(
+            interpret("format(3.14159:0:2)"),
+            Ok(InterpreterType::Str(text)) if text == "3.14"
+        ));
+        assert!(matches!(
+            interpret("format('x = ', 7:3, ', ', 1 / 4:6:3)"),
+            Ok(InterpreterType::Str(text)) if text == "x =   7,  0.250"
+        ));
+        assert_eq!(
+            interpret("format('a':0:2)").unwrap_err(),
+            "Decimals need a number, found a"
+        );
+        assert_eq!(
+            interpret("format(1:-1)").unwrap_err(),
+            "Invalid field width: -1"
+        );
+        assert_eq!(
+            interpret("sqrt(4:2)").unwrap_err(),
+            "Field widths only apply to write, writeln and format"
+        );
+    }
+
+    #[test]
+    fn test_repeat_runs_body_at_least_once() {
+        let source = "BEGIN n := 0; REPEAT n := n + 1 UNTIL TRUE END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert_eq!(interpreter.global_scope.get("n").unwrap().from::<i32>(), 1);
+    }
+
+    #[test]
+    fn test_repeat_until_condition_holds() {
+        let source =
+            "BEGIN n := 0; total := 0; REPEAT n := n + 1; total := total + n UNTIL n >= 5 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert_eq!(interpreter.global_scope.get("n").unwrap().from::<i32>(), 5);
+        assert_eq!(
+            interpreter.global_scope.get("total").unwrap().from::<i32>(),
+            15
+        );
+    }
+
+    #[test]
+    fn test_if_with_comparison() {
+        let mut lexer =
+            Lexer::new("BEGIN x := 7; IF x >= 5 THEN a := 1 ELSE a := 2 END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(interpreter.global_scope.get("a").unwrap().from::<i32>(), 1)
+    }
+
+    #[test]
+    fn test_if_expression() {
+        let mut lexer = Lexer::new("(IF 3 < 5 THEN 1 ELSE 0) + 10".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert!(matches!(
+            interpreter.interpret_typed(),
+            Ok(InterpreterType::Integer(11))
+        ));
+
+        // Only the chosen branch runs
+        let source = "BEGIN x := 0; y := IF x = 0 THEN 0 ELSE 10 DIV x END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert_eq!(interpreter.global_scope.get("y").unwrap().from::<i32>(), 0);
+    }
+
+    #[test]
+    fn test_arithmetic_on_boolean_is_an_error() {
+        let mut lexer = Lexer::new("(1 < 2) + 1".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "Expected a number, found TRUE"
+        );
+    }
+
+    #[test]
+    fn test_while_loop() {
+        let mut lexer = Lexer::new("BEGIN i := 0; WHILE i < 3 DO i := i + 1; END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(interpreter.global_scope.get("i").unwrap().from::<i32>(), 3)
+    }
+
+    #[test]
+    fn test_while_loop_with_compound_body() {
+        let mut lexer = Lexer::new(
+            "BEGIN i := 0; total := 0; WHILE i < 4 DO BEGIN i := i + 1; total := total + i END END."
+                .to_string(),
+        );
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(
+            interpreter.global_scope.get("total").unwrap().from::<i32>(),
+            10
+        )
+    }
+
+    #[test]
+    fn test_while_loop_that_never_runs() {
+        let mut lexer = Lexer::new("BEGIN i := 5; WHILE i < 3 DO i := i + 1 END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(interpreter.global_scope.get("i").unwrap().from::<i32>(), 5)
+    }
+
+    #[test]
+    fn test_for_loop() {
+        let mut lexer =
+            Lexer::new("BEGIN total := 0; FOR i := 1 TO 5 DO total := total + i END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(
+            interpreter.global_scope.get("total").unwrap().from::<i32>(),
+            15
+        )
+    }
+
+    #[test]
+    fn test_for_loop_downto() {
+        let mut lexer = Lexer::new(
+            "BEGIN digits := 0; FOR i := 3 DOWNTO 1 DO digits := digits * 10 + i END.".to_string(),
+        );
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(
+            interpreter
+                .global_scope
+                .get("digits")
+                .unwrap()
+                .from::<i32>(),
+            321
+        )
+    }
+
+    #[test]
+    fn test_for_loop_with_empty_range() {
+        let mut lexer =
+            Lexer::new("BEGIN total := 0; FOR i := 5 TO 1 DO total := total + i END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(
+            interpreter.global_scope.get("total").unwrap().from::<i32>(),
+            0
+        )
+    }
+
+    #[test]
+    fn test_for_loop_up_to_integer_limit() {
+        let mut lexer =
+            Lexer::new("BEGIN FOR i := 2147483646 TO 2147483647 DO x := i END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(
+            interpreter.global_scope.get("x").unwrap().from::<i32>(),
+            i32::MAX
+        )
+    }
+
+    #[test]
+    fn test_for_loop_with_real_bound_is_an_error() {
+        let mut lexer = Lexer::new("BEGIN FOR i := 1 TO 2.5 DO x := i END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "FOR bounds must be integers, found 2.5"
+        );
+    }
+
+    #[test]
+    fn test_procedure_call() {
+        let string = "
+            PROGRAM Procedures;
+            VAR x : INTEGER;
+            PROCEDURE SetX;
+            BEGIN
+               x := 42
+            END;
+            BEGIN
+               x := 1;
+               setx
+            END."
+            .to_string();
+        let mut lexer = Lexer::new(string);
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(interpreter.global_scope.get("x").unwrap().from::<i32>(), 42)
+    }
+
+    #[test]
+    fn test_procedure_call_with_parameters() {
+        let string = "
+            PROGRAM Procedures;
+            VAR total : INTEGER;
+            PROCEDURE Add(a, b : INTEGER);
+            BEGIN
+               total := a + b
+            END;
+            BEGIN
+               a := 100;
+               Add(2, 3)
+            END."
+            .to_string();
+        let mut lexer = Lexer::new(string);
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(
+            interpreter.global_scope.get("total").unwrap().from::<i32>(),
+            5
+        );
+        assert_eq!(
+            interpreter.global_scope.get("a").unwrap().from::<i32>(),
+            100
+        );
+        assert!(!interpreter.global_scope.contains_key("b"))
+    }
+
+    #[test]
+    fn test_procedure_local_variable_shadows_global() {
+        let string = "
+            PROGRAM Scopes;
+            VAR x, y : INTEGER;
+            PROCEDURE Inner;
+            VAR x : INTEGER;
+            BEGIN
+               x := 10;
+               y := x + 1
+            END;
+            BEGIN
+               x := 1;
+               Inner
+            END."
+            .to_string();
+        let mut lexer = Lexer::new(string);
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(interpreter.global_scope.get("x").unwrap().from::<i32>(), 1);
+        assert_eq!(interpreter.global_scope.get("y").unwrap().from::<i32>(), 11)
+    }
+
+    #[test]
+    fn test_nested_procedure_calls_resolve_innermost_first() {
+        let string = "
+            PROGRAM Scopes;
+            VAR seen : INTEGER;
+            PROCEDURE Show(n : INTEGER);
+            BEGIN
+               seen := n
+            END;
+            PROCEDURE Outer(n : INTEGER);
+            VAR temp : INTEGER;
+            BEGIN
+               temp := n * 2;
+               Show(temp + n)
+            END;
+            BEGIN
+               n := 100;
+               Outer(3)
+            END."
+            .to_string();
+        let mut lexer = Lexer::new(string);
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.0);
+        assert_eq!(
+            interpreter.global_scope.get("seen").unwrap().from::<i32>(),
+            9
+        );
+        assert_eq!(
+            interpreter.global_scope.get("n").unwrap().from::<i32>(),
+            100
+        );
+        assert!(!interpreter.global_scope.contains_key("temp"))
+    }
+
+    #[test]
+    fn test_procedure_call_errors() {
+        let string =
+            "PROGRAM P; PROCEDURE Add(a, b : INTEGER); BEGIN END; BEGIN Add(1) END.".to_string();
+        let mut lexer = Lexer::new(string);
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "Add expects 2 argument(s), got 1"
+        );
+
+        let mut lexer = Lexer::new("BEGIN Missing END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "Unknown procedure: Missing"
+        );
+    }
+
+    #[test]
+    fn test_procedure_parameters_keep_their_types() {
+        let head = "PROGRAM P; VAR s : REAL; PROCEDURE Show(x : INTEGER; r : REAL);";
+        let run = |body: &str, call: &str| {
+            let source = format!("{} BEGIN {} END; BEGIN {} END.", head, body, call);
+            let mut lexer = Lexer::new(source);
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            interpreter
+                .interpret()
+                .map(|_| interpreter.global_scope.get("s").unwrap().to_string())
+        };
+        assert_eq!(
+            run("", "Show('abc', 2)").unwrap_err(),
+            "Cannot assign abc to INTEGER variable x"
+        );
+        assert_eq!(run("s := r", "Show(1, 2)").unwrap(), "2.0");
+        assert_eq!(
+            run("x := 'oops'", "Show(1, 2)").unwrap_err(),
+            "Cannot assign oops to INTEGER variable x"
+        );
+    }
+
+    #[test]
+    fn test_with_program() {
+        let string = "
+            PROGRAM Part10;
+            VAR
+               x, y, z : INTEGER;
+            BEGIN {Part10}
+               BEGIN
+                   x := 5;
+                   y := x + 10;
+                   z := y DIV 3;
+               END;
+            END.  "
+            .to_string();
+        let mut lexer = Lexer::new(string);
         let mut parser = Parser::new(&mut lexer);
         let mut interpreter = Interpreter::new(&mut parser);
         assert_eq!(interpreter.interpret().unwrap(), 0.0);
@@ -346,4 +2012,780 @@ mod tests {
         assert_eq!(interpreter.global_scope.get("y").unwrap().from::<i32>(), 15);
         assert_eq!(interpreter.global_scope.get("z").unwrap().from::<i32>(), 5)
     }
+
+    #[test]
+    fn test_unknown_variable_is_an_error() {
+        let mut lexer = Lexer::new("x + 1".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap_err(), "Variable not found")
+    }
+
+    #[test]
+    fn test_lenient_unknown_variable_is_zero() {
+        let mut lexer = Lexer::new("x + 1".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.lenient = true;
+        assert_eq!(interpreter.interpret().unwrap(), 1.0)
+    }
+
+    #[test]
+    fn test_power() {
+        let mut lexer = Lexer::new("2 ** 10".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 1_024.0)
+    }
+
+    #[test]
+    fn test_power_with_caret() {
+        let mut lexer = Lexer::new("2 ^ 10".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 1_024.0)
+    }
+
+    #[test]
+    fn test_power_is_right_associative() {
+        let mut lexer = Lexer::new("2 ** 3 ** 2".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 512.0)
+    }
+
+    #[test]
+    fn test_power_binds_tighter_than_multiplication() {
+        let mut lexer = Lexer::new("2 * 3 ** 2".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 18.0)
+    }
+
+    #[test]
+    fn test_power_with_negative_exponent() {
+        let mut lexer = Lexer::new("2 ** -2".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 0.25)
+    }
+
+    #[test]
+    fn test_interpret_all() {
+        let mut lexer = Lexer::new("3+4; 5*6; 7-1".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        let values: Vec<i32> = interpreter
+            .interpret_all()
+            .unwrap()
+            .iter()
+            .map(|value| value.from::<i32>())
+            .collect();
+        assert_eq!(values, vec![7, 30, 6]);
+
+        let mut lexer = Lexer::new("x := 2; x * 10; 1 DIV 0; x".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert!(interpreter
+            .interpret_all()
+            .unwrap_err()
+            .starts_with("Division by zero"));
+    }
+
+    #[test]
+    fn test_backslash_divides_like_div() {
+        for input in ["7 \\ 2", "7 DIV 2"] {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert!(
+                matches!(
+                    interpreter.interpret_typed(),
+                    Ok(InterpreterType::Integer(3))
+                ),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_chained_assignment() {
+        let mut lexer = Lexer::new("BEGIN a := b := 5; END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        for name in ["a", "b"] {
+            assert!(matches!(
+                interpreter.global_scope.get(name),
+                Some(InterpreterType::Integer(5))
+            ));
+        }
+
+        // Each variable converts the value to its own type
+        let source = "PROGRAM P; VAR r : REAL; i : INTEGER; BEGIN r := i := 2 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert!(matches!(
+            interpreter.global_scope.get("r"),
+            Some(InterpreterType::Real(_))
+        ));
+        assert!(matches!(
+            interpreter.global_scope.get("i"),
+            Some(InterpreterType::Integer(2))
+        ));
+    }
+
+    #[test]
+    fn test_tree_can_be_evaluated_twice() {
+        let source = "PROGRAM P; VAR i, s : INTEGER; PROCEDURE Add(n : INTEGER); BEGIN s := s + n END; BEGIN FOR i := 1 TO 4 DO Add(i) END.";
+        let tree = crate::parse(source).unwrap();
+        let mut interpreter = Interpreter::default();
+        for _ in 0..2 {
+            interpreter.visit(&tree).unwrap();
+            assert!(matches!(
+                interpreter.global_scope.get("s"),
+                Some(InterpreterType::Integer(10))
+            ));
+        }
+
+        let tree = crate::parse("2 ** 10 - 24").unwrap();
+        let first = interpreter.visit(&tree).unwrap();
+        let second = interpreter.visit(&tree).unwrap();
+        assert_eq!(first.to_string(), "1000");
+        assert_eq!(second.to_string(), "1000");
+    }
+
+    #[test]
+    fn test_trace_shows_evaluation_order() {
+        let mut lexer = Lexer::new("3 + 4 * 2".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.trace = true;
+        interpreter.interpret().unwrap();
+        let log = interpreter.trace_log();
+        let position = |line: &str| log.iter().position(|entry| entry == line).unwrap();
+        let multiplied = position("  visit BinaryOp(*) -> 8");
+        let added = position("visit BinaryOp(+) -> 11");
+        assert!(multiplied < added);
+        assert_eq!(log[0], "visit BinaryOp(+)");
+        assert_eq!(log.len(), 10);
+
+        let mut interpreter = Interpreter::default();
+        let tree = crate::parse("1 + 2").unwrap();
+        interpreter.visit(&tree).unwrap();
+        assert!(interpreter.trace_log().is_empty());
+    }
+
+    #[test]
+    fn test_several_var_sections() {
+        let source =
+            "PROGRAM P; VAR a : INTEGER; CONST K = 10; VAR b : REAL; BEGIN a := K; b := a / 4 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert!(matches!(
+            interpreter.global_scope.get("a"),
+            Some(InterpreterType::Integer(10))
+        ));
+        assert!(matches!(
+            interpreter.global_scope.get("b"),
+            Some(InterpreterType::Real(value)) if *value == 2.5
+        ));
+    }
+
+    #[test]
+    fn test_characters() {
+        let interpret = |source: &str| {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            interpreter.interpret_typed()
+        };
+        assert!(matches!(
+            interpret("ord('A')"),
+            Ok(InterpreterType::Integer(65))
+        ));
+        assert!(matches!(
+            interpret("chr(66)"),
+            Ok(InterpreterType::Char('B'))
+        ));
+        assert!(matches!(
+            interpret("ord(chr(90))"),
+            Ok(InterpreterType::Integer(90))
+        ));
+        assert!(matches!(interpret("#67"), Ok(InterpreterType::Char('C'))));
+        assert!(matches!(interpret("#72 + 'i'"), Ok(InterpreterType::Str(value)) if value == "Hi"));
+        assert!(matches!(
+            interpret("chr(97) = 'a'"),
+            Ok(InterpreterType::Boolean(true))
+        ));
+        assert_eq!(
+            interpret("chr(-1)").unwrap_err(),
+            "Character code out of range: -1"
+        );
+        assert_eq!(
+            interpret("ord('AB')").unwrap_err(),
+            "ord expects a character, found AB"
+        );
+        assert_eq!(
+            interpret("#65 * 2").unwrap_err(),
+            "Expected a number, found 'A'"
+        );
+    }
+
+    #[test]
+    fn test_char_arithmetic() {
+        let interpret = |source: &str| {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            interpreter.interpret_typed()
+        };
+        let source = "PROGRAM P; VAR c, d : CHAR; BEGIN c := 'A'; d := c + 1 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert!(matches!(
+            interpreter.global_scope.get("d"),
+            Some(InterpreterType::Char('B'))
+        ));
+        assert!(matches!(
+            interpret("#90 - #65"),
+            Ok(InterpreterType::Integer(25))
+        ));
+        assert!(matches!(
+            interpret("2 + chr(97)"),
+            Ok(InterpreterType::Char('c'))
+        ));
+        assert!(matches!(
+            interpret("#66 - 1"),
+            Ok(InterpreterType::Char('A'))
+        ));
+        assert!(matches!(interpret("'a' + 'b'"), Ok(InterpreterType::Str(value)) if value == "ab"));
+        // One-character literals are strings, not characters
+        assert!(matches!(interpret("'A' + 'A'"), Ok(InterpreterType::Str(value)) if value == "AA"));
+        assert_eq!(
+            interpret("'f' + 3").unwrap_err(),
+            "Cannot concatenate 'f' and 3"
+        );
+        assert_eq!(
+            interpret("#65 + #66").unwrap_err(),
+            "Cannot add 'A' and 'B'"
+        );
+        assert_eq!(
+            interpret("#0 - 1").unwrap_err(),
+            "Character code out of range: -1"
+        );
+    }
+
+    #[test]
+    fn test_char_variables() {
+        let source = "PROGRAM P; VAR c : CHAR; n : INTEGER; BEGIN n := ord(c); c := 'x' END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert!(matches!(
+            interpreter.global_scope.get("c"),
+            Some(InterpreterType::Char('x'))
+        ));
+        assert!(matches!(
+            interpreter.global_scope.get("n"),
+            Some(InterpreterType::Integer(0))
+        ));
+
+        let source = "PROGRAM P; VAR c : CHAR; BEGIN c := 'xy' END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "Cannot assign xy to CHAR variable c"
+        );
+    }
+
+    #[test]
+    fn test_div_and_mod_associate_left() {
+        let cases = [
+            ("20 DIV 3 MOD 2", 0),
+            ("2 * 3 DIV 2", 3),
+            ("20 MOD 7 DIV 2", 3),
+        ];
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert!(
+                matches!(interpreter.interpret_typed(), Ok(InterpreterType::Integer(value)) if value == expected),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_arrays() {
+        let source = "PROGRAM P; VAR a : ARRAY[1..5] OF INTEGER; i : INTEGER; BEGIN a[2] := 7; FOR i := 3 TO 5 DO a[i] := a[i - 1] * 2; x := a[2] + a[5] END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert!(matches!(
+            interpreter.global_scope.get("x"),
+            Some(InterpreterType::Integer(63))
+        ));
+        assert_eq!(
+            interpreter.global_scope.get("a").unwrap().to_string(),
+            "[0, 7, 14, 28, 56]"
+        );
+    }
+
+    #[test]
+    fn test_array_assignment_copies() {
+        let source = "PROGRAM P; VAR a, b : ARRAY[1..3] OF INTEGER; BEGIN b[1] := 1; a := b; b[1] := 2; b[3] := 3 END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.interpret().unwrap();
+        assert_eq!(
+            interpreter.global_scope.get("a").unwrap().to_string(),
+            "[1, 0, 0]"
+        );
+        assert_eq!(
+            interpreter.global_scope.get("b").unwrap().to_string(),
+            "[2, 0, 3]"
+        );
+    }
+
+    #[test]
+    fn test_array_errors() {
+        let cases = [
+            ("a[6] := 1", "Index 6 out of bounds for a[1..5]"),
+            ("x := a[0]", "Index 0 out of bounds for a[1..5]"),
+            ("a[1] := 2.5", "Cannot assign 2.5 to INTEGER variable a"),
+            ("a := 1", "Cannot assign 1 to ARRAY variable a"),
+            (
+                "b := a",
+                "Cannot assign [0, 0, 0, 0, 0] to b: the bounds differ",
+            ),
+            (
+                "a := c",
+                "Cannot assign [0.0, 0.0, 0.0, 0.0, 0.0] to a: the element types differ",
+            ),
+            (
+                "d := e",
+                "Cannot assign ['\0', '\0'] to d: the element types differ",
+            ),
+            ("x := a + 1", "Expected a number, found [0, 0, 0, 0, 0]"),
+            ("x[1] := 1", "x is not an array: 0"),
+        ];
+        for (statement, expected) in cases {
+            let source = format!(
+                "PROGRAM P; VAR a : ARRAY[1..5] OF INTEGER; b : ARRAY[0..1] OF INTEGER; c : ARRAY[1..5] OF REAL; d : ARRAY[1..2] OF BOOLEAN; e : ARRAY[1..2] OF CHAR; x : INTEGER; BEGIN {} END.",
+                statement
+            );
+            let mut lexer = Lexer::new(source);
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert_eq!(
+                interpreter.interpret().unwrap_err(),
+                expected,
+                "{}",
+                statement
+            );
+        }
+    }
+
+    #[test]
+    fn test_power_result_types() {
+        let interpret = |source: &str| {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            interpreter.interpret_typed()
+        };
+        assert!(matches!(
+            interpret("2 ** 3"),
+            Ok(InterpreterType::Integer(8))
+        ));
+        assert!(matches!(interpret("2 ** -2"), Ok(InterpreterType::Real(value)) if value == 0.25));
+        assert!(matches!(interpret("9 ** 0.5"), Ok(InterpreterType::Real(value)) if value == 3.0));
+        assert!(matches!(interpret("2.0 ** 3"), Ok(InterpreterType::Real(value)) if value == 8.0));
+        assert!(matches!(
+            interpret("(-2) ** 31"),
+            Ok(InterpreterType::Integer(i32::MIN))
+        ));
+        assert_eq!(interpret("2 ** 31").unwrap_err(), "Integer overflow");
+        assert_eq!(
+            interpret("0 ** -1").unwrap_err(),
+            "Division by zero at line 1, column 3"
+        );
+        assert_eq!(
+            interpret("0.0 ^ -0.5").unwrap_err(),
+            "Division by zero at line 1, column 5"
+        );
+        assert_eq!(
+            interpret("(-8) ** (1 / 3)").unwrap_err(),
+            "-8 ** 0.3333333333333333 is undefined"
+        );
+        assert!(matches!(
+            interpret("0 ** 0"),
+            Ok(InterpreterType::Integer(1))
+        ));
+        assert!(
+            matches!(interpret("(-8) ** -1"), Ok(InterpreterType::Real(value)) if value == -0.125)
+        );
+        assert!(
+            matches!(interpret("2.0 ** 31"), Ok(InterpreterType::Real(value)) if value == 2147483648.0)
+        );
+    }
+
+    #[test]
+    fn test_multiplication_is_not_power() {
+        let mut lexer = Lexer::new("2 * 3".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 6.0)
+    }
+
+    #[test]
+    fn test_deep_expression_exceeds_max_depth() {
+        let input = format!("{}1{}", "1 + (".repeat(100), ")".repeat(100));
+        let mut lexer = Lexer::new(input);
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.max_depth = 50;
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "Maximum recursion depth exceeded"
+        );
+    }
+
+    #[test]
+    fn test_recursion_is_limited_by_call_depth() {
+        let source = "PROGRAM P; VAR s : INTEGER;
+            PROCEDURE R(n : INTEGER); BEGIN IF n > 0 THEN BEGIN s := s + 1; R(n - 1) END END;
+            BEGIN R(100) END.";
+        let run = |max_depth, max_call_depth| {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            interpreter.max_depth = max_depth;
+            interpreter.max_call_depth = max_call_depth;
+            interpreter
+                .interpret()
+                .map(|_| interpreter.global_scope.get("s").unwrap().to_string())
+        };
+        // Visits only count within a call, so deep recursion fits a small
+        // `max_depth`
+        assert_eq!(run(20, DEFAULT_MAX_CALL_DEPTH).unwrap(), "100");
+        assert_eq!(run(20, 50).unwrap_err(), "Maximum call depth exceeded");
+    }
+
+    #[test]
+    fn test_deep_expression_within_max_depth() {
+        let input = format!("{}1{}", "1 + (".repeat(100), ")".repeat(100));
+        let mut lexer = Lexer::new(input);
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 101.0);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let mut lexer = Lexer::new("lerp(0, 10, 0.5)".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 5.0)
+    }
+
+    #[test]
+    fn test_clamp01() {
+        let mut lexer = Lexer::new("clamp01(1.5)".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 1.0)
+    }
+
+    #[test]
+    fn test_lerp_and_clamp01_need_numbers() {
+        let cases = [
+            ("clamp01('x')", "clamp01 expects a number, found x"),
+            ("lerp(TRUE, 'a', 1)", "lerp expects a number, found TRUE"),
+            ("lerp(0, 1, #65)", "lerp expects a number, found A"),
+        ];
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert_eq!(interpreter.interpret().unwrap_err(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_math_functions() {
+        let cases = [
+            ("sqrt(16)", 4.0),
+            ("abs(-3)", 3.0),
+            ("abs(2.5)", 2.5),
+            ("sin(0)", 0.0),
+            ("cos(0)", 1.0),
+            ("ln(exp(2))", 2.0),
+        ];
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert_eq!(interpreter.interpret().unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_abs_keeps_integers() {
+        let mut lexer = Lexer::new("abs(-3)".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert!(matches!(
+            interpreter.interpret_typed(),
+            Ok(InterpreterType::Integer(3))
+        ));
+    }
+
+    #[test]
+    fn test_rounding_functions() {
+        let interpret = |source: &str| {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            interpreter.interpret_typed()
+        };
+        let cases = [
+            ("trunc(3.7)", 3),
+            ("trunc(-3.7)", -3),
+            ("round(2.5)", 3),
+            ("round(-2.5)", -3),
+            ("round(2.4)", 2),
+            ("floor(-1.5)", -2),
+            ("ceil(1.2)", 2),
+            ("round(7)", 7),
+            ("abs(-4)", 4),
+            ("sign(-4)", -1),
+            ("sign(0)", 0),
+        ];
+        for (input, expected) in cases {
+            assert!(
+                matches!(interpret(input), Ok(InterpreterType::Integer(value)) if value == expected),
+                "{}",
+                input
+            );
+        }
+        assert!(matches!(interpret("sign(0.5)"), Ok(InterpreterType::Real(value)) if value == 1.0));
+        assert!(matches!(interpret("abs(-0.5)"), Ok(InterpreterType::Real(value)) if value == 0.5));
+        assert_eq!(interpret("round(1e10)").unwrap_err(), "Integer overflow");
+        assert_eq!(
+            interpret("trunc(1, 2)").unwrap_err(),
+            "trunc expects 1 argument(s), got 2"
+        );
+        assert_eq!(
+            interpret("floor('a')").unwrap_err(),
+            "floor expects a number, found a"
+        );
+    }
+
+    #[test]
+    fn test_math_function_domain_errors() {
+        let mut lexer = Lexer::new("sqrt(-1)".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "sqrt is undefined for -1"
+        );
+
+        let mut lexer = Lexer::new("ln(0)".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "ln is undefined for 0"
+        );
+    }
+
+    #[test]
+    fn test_unknown_function() {
+        let mut lexer = Lexer::new("foo(1)".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "Unknown function: foo"
+        )
+    }
+
+    #[test]
+    fn test_case_insensitive_variables() {
+        let mut lexer = Lexer::new("BEGIN X := 1; y := x END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert!(interpreter.interpret().is_ok());
+        assert_eq!(interpreter.global_scope.get("y").unwrap().from::<i32>(), 1)
+    }
+
+    #[test]
+    fn test_case_sensitive_variables() {
+        let mut lexer = Lexer::new("BEGIN X := 1; y := x END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.case_sensitive = true;
+        assert_eq!(interpreter.interpret().unwrap_err(), "Variable not found")
+    }
+
+    #[test]
+    fn test_bignum_factorial() {
+        let factors: Vec<String> = (1..=25).map(|i| i.to_string()).collect();
+        let mut lexer = Lexer::new(factors.join(" * "));
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.bignum = true;
+        assert_eq!(
+            interpreter.interpret_typed().unwrap().to_string(),
+            "15511210043330985984000000"
+        );
+    }
+
+    #[test]
+    fn test_bignum_power_and_division() {
+        let mut lexer = Lexer::new("2 ** 100 DIV 2 ** 98".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.bignum = true;
+        assert_eq!(interpreter.interpret_typed().unwrap().to_string(), "4");
+    }
+
+    #[test]
+    fn test_overflow_without_bignum() {
+        let mut lexer = Lexer::new("2147483647 + 1".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap_err(), "Integer overflow");
+    }
+
+    #[test]
+    fn test_overflowing_multiplication() {
+        for input in ["2000000000 * 2", "0 - 2147483647 - 2", "-(2000000000 * -2)"] {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert_eq!(
+                interpreter.interpret().unwrap_err(),
+                "Integer overflow",
+                "{}",
+                input
+            );
+        }
+
+        // With bignum on the same product is exact
+        let mut lexer = Lexer::new("2000000000 * 2".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.bignum = true;
+        assert_eq!(
+            interpreter.interpret_typed().unwrap().to_string(),
+            "4000000000"
+        );
+    }
+
+    #[test]
+    fn test_division_by_zero_reports_position() {
+        let mut lexer = Lexer::new("1 + 2 DIV 0".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "Division by zero at line 1, column 7"
+        );
+    }
+
+    #[test]
+    fn test_division_by_zero_is_an_error() {
+        for input in ["5 DIV 0", "5 / 0", "5 / 0.0", "5 MOD 0"] {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert!(interpreter
+                .interpret()
+                .unwrap_err()
+                .starts_with("Division by zero"));
+        }
+    }
+
+    #[test]
+    fn test_modulo_by_zero_reports_position() {
+        let mut lexer = Lexer::new("5 MOD 0".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "Division by zero at line 1, column 3"
+        );
+    }
+
+    #[test]
+    fn test_registered_function() {
+        let mut lexer = Lexer::new("double(4) + double(0.25)".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.register_function("double", |args| match args {
+            [value] => value.clone().checked_mul(InterpreterType::Integer(2)),
+            _ => Err("double expects 1 argument".to_string()),
+        });
+        assert_eq!(interpreter.interpret().unwrap(), 8.5)
+    }
+
+    #[test]
+    fn test_registered_function_overrides_builtin() {
+        let mut lexer = Lexer::new("clamp01(5)".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.register_function("clamp01", |_| Ok(InterpreterType::Integer(7)));
+        assert_eq!(interpreter.interpret().unwrap(), 7.0)
+    }
+
+    #[test]
+    fn test_function_names_ignore_case() {
+        let cases = [
+            ("SQRT(16)", 4.0),
+            ("Abs(-2)", 2.0),
+            ("PI()", std::f64::consts::PI),
+        ];
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            let mut interpreter = Interpreter::new(&mut parser);
+            assert_eq!(interpreter.interpret().unwrap(), expected, "{}", input);
+        }
+
+        let mut lexer = Lexer::new("Double(4)".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.register_function("DOUBLE", |args| {
+            args[0].clone().checked_mul(args[0].clone())
+        });
+        assert_eq!(interpreter.interpret().unwrap(), 16.0);
+
+        // Strict mode keeps names as written, like variables
+        let mut lexer = Lexer::new("SQRT(16)".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        interpreter.case_sensitive = true;
+        assert_eq!(
+            interpreter.interpret().unwrap_err(),
+            "Unknown function: SQRT"
+        );
+    }
 }
@@ -1,29 +1,71 @@
+use std::{error::Error, fmt};
+
+use crate::interpreter::BigInt;
 use crate::token::{Token, TokenKind, TokenValue, RESERVED_KEYWORDS};
 
 #[derive(Debug, Clone)]
 pub struct Lexer {
     pub text: String,
+    // The characters of `text`, collected once so that `pos` can index them
+    chars: Vec<char>,
     pub pos: usize,
     pub current_char: char,
+    pub token_start: usize,
+    pub line: usize,
+    pub column: usize,
+    token_line: usize,
+    token_column: usize,
+    // Lex integer literals too large for an Integer as BigInts instead of
+    // rejecting them, for interpreters running in bignum mode
+    pub bignum: bool,
 }
 
+#[derive(Debug, Clone)]
+pub struct LexerError {
+    pub message: String,
+}
+
+impl fmt::Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LexerError: {}", self.message)
+    }
+}
+
+impl Error for LexerError {}
+
 impl Lexer {
     pub fn new(text: String) -> Lexer {
-        let t = text.clone();
+        let chars: Vec<char> = text.chars().collect();
+        let current_char = chars.first().copied().unwrap_or('\0');
         Lexer {
             text,
+            chars,
             pos: 0,
-            current_char: t.chars().nth(0).unwrap(),
+            current_char,
+            token_start: 0,
+            line: 1,
+            column: 1,
+            token_line: 1,
+            token_column: 1,
+            bignum: false,
         }
     }
 
     fn advance(&mut self) {
-        self.pos += 1;
-        if self.pos > self.text.len() - 1 {
-            self.current_char = '\0';
+        if self.current_char == '\n' {
+            self.line += 1;
+            self.column = 1;
         } else {
-            self.current_char = self.text.chars().nth(self.pos).unwrap();
+            self.column += 1;
         }
+        self.pos += 1;
+        self.current_char = self.chars.get(self.pos).copied().unwrap_or('\0');
+    }
+
+    fn mark_token_start(&mut self) {
+        self.token_start = self.pos;
+        self.token_line = self.line;
+        self.token_column = self.column;
     }
 
     fn skip_whitespace(&mut self) {
@@ -32,49 +74,124 @@ impl Lexer {
         }
     }
 
-    fn number(&mut self) -> Token {
+    fn number(&mut self) -> Result<Token, LexerError> {
         let mut result = String::new();
-        while self.current_char != '\0' && self.current_char.is_numeric() {
+        while self.current_char.is_ascii_digit() {
             result.push(self.current_char);
             self.advance();
+            // Underscores may separate digit groups, as in 1_000
+            while self.current_char == '_' && self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.advance();
+            }
         }
 
-        if self.current_char == '.' {
+        let mut real = false;
+        // A '.' not followed by a digit is the program terminator, not a decimal point
+        if self.current_char == '.' && self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            real = true;
             result.push(self.current_char);
             self.advance();
-            while self.current_char != '\0' && self.current_char.is_numeric() {
+            while self.current_char.is_ascii_digit() {
                 result.push(self.current_char);
                 self.advance();
             }
-            Token::new(
-                TokenKind::Real,
-                TokenValue::Real(result.parse::<f64>().unwrap()),
-            )
+        }
+
+        // An exponent, as in 1.5e3 or 2E-2, always makes the literal real
+        if self.current_char == 'e' || self.current_char == 'E' {
+            real = true;
+            result.push(self.current_char);
+            self.advance();
+            if self.current_char == '+' || self.current_char == '-' {
+                result.push(self.current_char);
+                self.advance();
+            }
+            if !self.current_char.is_ascii_digit() {
+                return Err(self.error("malformed exponent in number"));
+            }
+            while self.current_char.is_ascii_digit() {
+                result.push(self.current_char);
+                self.advance();
+            }
+        }
+
+        if real {
+            match result.parse::<f64>() {
+                Ok(value) => Ok(Token::new(TokenKind::Real, TokenValue::Real(value))),
+                Err(_) => Err(self.error(&format!("invalid number {}", result))),
+            }
         } else {
-            Token::new(
-                TokenKind::Integer,
-                TokenValue::Int(result.parse::<i32>().unwrap()),
-            )
+            match result.parse::<i32>() {
+                Ok(value) => Ok(Token::new(TokenKind::Integer, TokenValue::Int(value))),
+                Err(_) if self.bignum => match BigInt::from_digits(&result) {
+                    Some(value) => Ok(Token::new(TokenKind::Integer, TokenValue::BigInt(value))),
+                    None => Err(self.error(&format!("invalid number {}", result))),
+                },
+                Err(_) => Err(self.error(&format!("integer literal {} is too large", result))),
+            }
         }
     }
 
-    fn peek(&self) -> Option<char> {
-        let peek_pos = self.pos + 1;
-        if peek_pos > self.text.len() - 1 {
-            None
-        } else {
-            Some(self.text.chars().nth(peek_pos).unwrap())
+    fn string(&mut self) -> Result<Token, LexerError> {
+        // Skip the opening quote; a doubled quote inside stands for one quote
+        self.advance();
+        let mut result = String::new();
+        loop {
+            match self.current_char {
+                '\0' => return Err(self.error("unterminated string")),
+                '\'' if self.peek() == Some('\'') => {
+                    result.push('\'');
+                    self.advance();
+                    self.advance();
+                }
+                '\'' => {
+                    self.advance();
+                    break;
+                }
+                c => {
+                    result.push(c);
+                    self.advance();
+                }
+            }
+        }
+        Ok(Token::new(
+            TokenKind::StringLiteral,
+            TokenValue::Str(result),
+        ))
+    }
+
+    fn char_literal(&mut self) -> Result<Token, LexerError> {
+        // `#65` is the character with code 65
+        self.advance();
+        let mut digits = String::new();
+        while self.current_char.is_ascii_digit() {
+            digits.push(self.current_char);
+            self.advance();
+        }
+        match digits.parse::<u32>().ok().and_then(char::from_u32) {
+            Some(c) => Ok(Token::new(
+                TokenKind::CharLiteral,
+                TokenValue::Int(c as i32),
+            )),
+            None if digits.is_empty() => Err(self.error("malformed character code")),
+            None => Err(self.error(&format!("invalid character code #{}", digits))),
         }
     }
 
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos + 1).copied()
+    }
+
     fn id(&mut self) -> Token {
         let mut result = String::new();
         while self.current_char != '\0' && self.current_char.is_alphanumeric() {
             result.push(self.current_char);
             self.advance();
         }
+        // Keywords are case-insensitive; the token keeps the text as written
+        let upper = result.to_uppercase();
         for (kind, value) in RESERVED_KEYWORDS.iter() {
-            if result == *value {
+            if upper == *value {
                 let kind = kind.clone();
                 return Token::new(kind, TokenValue::Str(result));
             }
@@ -82,8 +199,34 @@ impl Lexer {
         Token::new(TokenKind::Identifier, TokenValue::Str(result))
     }
 
-    pub fn get_next_token(&mut self) -> Option<Token> {
+    fn error(&self, message: &str) -> LexerError {
+        LexerError {
+            message: format!(
+                "{} at line {}, column {}",
+                message, self.token_line, self.token_column
+            ),
+        }
+    }
+
+    pub fn get_next_token(&mut self) -> Result<Token, LexerError> {
+        let mut token = self.scan_token()?;
+        token.lexeme = self.chars[self.token_start..self.pos].iter().collect();
+        token.line = self.token_line;
+        token.column = self.token_column;
+        Ok(token)
+    }
+
+    // Iterates over the remaining tokens, not including the final EOF.
+    pub fn tokens(&mut self) -> Tokens<'_> {
+        Tokens {
+            lexer: self,
+            done: false,
+        }
+    }
+
+    fn scan_token(&mut self) -> Result<Token, LexerError> {
         while self.current_char != '\0' {
+            self.mark_token_start();
             if self.current_char.is_whitespace() {
                 self.skip_whitespace();
                 continue;
@@ -91,102 +234,222 @@ impl Lexer {
 
             if self.current_char == '{' {
                 self.advance();
-                self.skip_comment();
+                self.skip_comment()?;
+                continue;
+            }
+
+            if self.current_char == '/' && self.peek() == Some('/') {
+                self.skip_line_comment();
                 continue;
             }
 
             if self.current_char.is_alphabetic() {
-                return Some(self.id());
+                return Ok(self.id());
+            }
+
+            // A '.' followed by a digit starts a real like .5; any other '.'
+            // is punctuation, such as the one after the final END
+            if self.current_char.is_ascii_digit()
+                || (self.current_char == '.' && self.peek().is_some_and(|c| c.is_ascii_digit()))
+            {
+                return self.number();
             }
 
-            if self.current_char.is_numeric() {
-                return Some(self.number());
+            if self.current_char == '\'' {
+                return self.string();
+            }
+
+            if self.current_char == '#' {
+                return self.char_literal();
             }
 
             match self.current_char {
                 '+' => {
                     self.advance();
-                    return Some(Token::new(
+                    return Ok(Token::new(
                         TokenKind::Plus,
                         TokenValue::Str("+".to_string()),
                     ));
                 }
                 '-' => {
                     self.advance();
-                    return Some(Token::new(
+                    return Ok(Token::new(
                         TokenKind::Minus,
                         TokenValue::Str("-".to_string()),
                     ));
                 }
+                '*' if self.peek() == Some('*') => {
+                    self.advance();
+                    self.advance();
+                    let symbol = TokenValue::Str("**".to_string());
+                    return Ok(Token::new(TokenKind::Power, symbol));
+                }
+                '^' => {
+                    self.advance();
+                    let symbol = TokenValue::Str("^".to_string());
+                    return Ok(Token::new(TokenKind::Power, symbol));
+                }
                 '*' => {
                     self.advance();
-                    return Some(Token::new(
+                    return Ok(Token::new(
                         TokenKind::Multiply,
                         TokenValue::Str("*".to_string()),
                     ));
                 }
                 '/' => {
                     self.advance();
-                    return Some(Token::new(
+                    return Ok(Token::new(
                         TokenKind::FloatDivide,
                         TokenValue::Str("/".to_string()),
                     ));
                 }
+                // A symbolic spelling of DIV
+                '\\' => {
+                    self.advance();
+                    let symbol = TokenValue::Str("\\".to_string());
+                    return Ok(Token::new(TokenKind::IntegerDivide, symbol));
+                }
                 '(' => {
                     self.advance();
-                    return Some(Token::new(
+                    return Ok(Token::new(
                         TokenKind::LParen,
                         TokenValue::Str("(".to_string()),
                     ));
                 }
                 ')' => {
                     self.advance();
-                    return Some(Token::new(
+                    return Ok(Token::new(
                         TokenKind::RParen,
                         TokenValue::Str(")".to_string()),
                     ));
                 }
+                '<' if self.peek() == Some('=') => {
+                    self.advance();
+                    self.advance();
+                    let symbol = TokenValue::Str("<=".to_string());
+                    return Ok(Token::new(TokenKind::LessEqual, symbol));
+                }
+                '<' if self.peek() == Some('>') => {
+                    self.advance();
+                    self.advance();
+                    let symbol = TokenValue::Str("<>".to_string());
+                    return Ok(Token::new(TokenKind::NotEqual, symbol));
+                }
+                '<' => {
+                    self.advance();
+                    let symbol = TokenValue::Str("<".to_string());
+                    return Ok(Token::new(TokenKind::LessThan, symbol));
+                }
+                '>' if self.peek() == Some('=') => {
+                    self.advance();
+                    self.advance();
+                    let symbol = TokenValue::Str(">=".to_string());
+                    return Ok(Token::new(TokenKind::GreaterEqual, symbol));
+                }
+                '>' => {
+                    self.advance();
+                    let symbol = TokenValue::Str(">".to_string());
+                    return Ok(Token::new(TokenKind::GreaterThan, symbol));
+                }
+                '=' => {
+                    self.advance();
+                    let symbol = TokenValue::Str("=".to_string());
+                    return Ok(Token::new(TokenKind::Equal, symbol));
+                }
                 ':' if self.peek() == Some('=') => {
                     self.advance();
                     self.advance();
                     let symbol = TokenValue::Str(":=".to_string());
-                    return Some(Token::new(TokenKind::Assign, symbol));
+                    return Ok(Token::new(TokenKind::Assign, symbol));
                 }
                 ':' => {
                     self.advance();
                     let symbol = TokenValue::Str(":".to_string());
-                    return Some(Token::new(TokenKind::Colon, symbol));
+                    return Ok(Token::new(TokenKind::Colon, symbol));
                 }
                 ';' => {
                     self.advance();
                     let symbol = TokenValue::Str(";".to_string());
-                    return Some(Token::new(TokenKind::Semi, symbol));
+                    return Ok(Token::new(TokenKind::Semi, symbol));
+                }
+                '.' if self.peek() == Some('.') => {
+                    self.advance();
+                    self.advance();
+                    let symbol = TokenValue::Str("..".to_string());
+                    return Ok(Token::new(TokenKind::DotDot, symbol));
                 }
                 '.' => {
                     self.advance();
                     let symbol = TokenValue::Str(".".to_string());
-                    return Some(Token::new(TokenKind::Dot, symbol));
+                    return Ok(Token::new(TokenKind::Dot, symbol));
+                }
+                '[' => {
+                    self.advance();
+                    let symbol = TokenValue::Str("[".to_string());
+                    return Ok(Token::new(TokenKind::LBracket, symbol));
+                }
+                ']' => {
+                    self.advance();
+                    let symbol = TokenValue::Str("]".to_string());
+                    return Ok(Token::new(TokenKind::RBracket, symbol));
                 }
                 ',' => {
                     self.advance();
                     let symbol = TokenValue::Str(",".to_string());
-                    return Some(Token::new(TokenKind::Comma, symbol));
-                }
-                _ => {
-                    let symbol = TokenValue::Str("".to_string());
-                    return Some(Token::new(TokenKind::EOF, symbol));
+                    return Ok(Token::new(TokenKind::Comma, symbol));
                 }
+                c => return Err(self.error(&format!("unexpected character '{}'", c))),
             }
         }
+        self.mark_token_start();
         let symbol = TokenValue::Str("".to_string());
-        Some(Token::new(TokenKind::EOF, symbol))
+        Ok(Token::new(TokenKind::EOF, symbol))
+    }
+
+    fn skip_line_comment(&mut self) {
+        while self.current_char != '\n' && self.current_char != '\0' {
+            self.advance();
+        }
     }
 
-    fn skip_comment(&mut self) {
+    fn skip_comment(&mut self) -> Result<(), LexerError> {
         while self.current_char != '}' {
+            if self.current_char == '\0' {
+                return Err(self.error("unterminated comment"));
+            }
             self.advance();
         }
         self.advance();
+        Ok(())
+    }
+}
+
+// Yields each token in turn and stops at EOF, which is not yielded. A lexer
+// error is yielded once and ends the iteration, so
+// `collect::<Result<Vec<_>, _>>()` gives either every token or the error.
+pub struct Tokens<'a> {
+    lexer: &'a mut Lexer,
+    done: bool,
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Result<Token, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.lexer.get_next_token() {
+            Ok(token) if token.kind == TokenKind::EOF => {
+                self.done = true;
+                None
+            }
+            Ok(token) => Some(Ok(token)),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
     }
 }
 
@@ -371,8 +634,9 @@ mod tests {
         let token = lexer.get_next_token().unwrap();
         assert_eq!(token.kind, TokenKind::Var);
 
+        // Keywords ignore case, so `Integer` is the type name, not an identifier
         let token = lexer.get_next_token().unwrap();
-        assert_eq!(token.kind, TokenKind::Identifier);
+        assert_eq!(token.kind, TokenKind::Integer);
 
         let token = lexer.get_next_token().unwrap();
         assert_eq!(token.kind, TokenKind::Colon);
@@ -386,4 +650,374 @@ mod tests {
         let token = lexer.get_next_token().unwrap();
         assert_eq!(token.kind, TokenKind::EOF);
     }
+
+    #[test]
+    fn test_power() {
+        let mut lexer = Lexer::new("2 ** 3 * 4 ^ 5".to_string());
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Integer);
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Power);
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Integer);
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Multiply);
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Integer);
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Power);
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Integer);
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::EOF);
+    }
+
+    #[test]
+    fn test_lexeme() {
+        let mut lexer = Lexer::new("1_000 + 2.50".to_string());
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.lexeme, "1_000");
+        assert_eq!(token.value, TokenValue::Int(1000));
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.lexeme, "+");
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.lexeme, "2.50");
+        assert_eq!(token.value, TokenValue::Real(2.5));
+    }
+
+    #[test]
+    fn test_token_line_and_column() {
+        let mut lexer = Lexer::new("BEGIN\n  a := 1 {one\ntwo} + 2\nEND.".to_string());
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!((token.line, token.column), (1, 1));
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!((token.line, token.column), (2, 3));
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!((token.line, token.column), (2, 5));
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!((token.line, token.column), (2, 8));
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Plus);
+        assert_eq!((token.line, token.column), (3, 6));
+
+        lexer.get_next_token().unwrap();
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::End);
+        assert_eq!((token.line, token.column), (4, 1));
+    }
+
+    #[test]
+    fn test_integer_followed_by_dot() {
+        let mut lexer = Lexer::new("5.".to_string());
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Integer);
+        assert_eq!(token.value, TokenValue::Int(5));
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Dot);
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::EOF);
+    }
+
+    #[test]
+    fn test_real_literal() {
+        let mut lexer = Lexer::new("5.5".to_string());
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Real);
+        assert_eq!(token.value, TokenValue::Real(5.5));
+
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::EOF);
+    }
+
+    #[test]
+    fn test_real_with_leading_dot() {
+        let mut lexer = Lexer::new(".5 + .25e1 END.\n".to_string());
+        let tokens: Vec<_> = lexer.tokens().map(|token| token.unwrap()).collect();
+        assert_eq!(tokens[0].value, TokenValue::Real(0.5));
+        assert_eq!(tokens[0].lexeme, ".5");
+        assert_eq!(tokens[2].value, TokenValue::Real(2.5));
+        assert_eq!(tokens[3].kind, TokenKind::End);
+        assert_eq!(tokens[4].kind, TokenKind::Dot);
+        assert_eq!(tokens.len(), 5);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let mut lexer = Lexer::new("".to_string());
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::EOF);
+    }
+
+    #[test]
+    fn test_whitespace_only_input() {
+        let mut lexer = Lexer::new("  \n ".to_string());
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::EOF);
+    }
+
+    #[test]
+    fn test_comparison_operators() {
+        let mut lexer = Lexer::new("< <= <> > >= = :=".to_string());
+        let kinds = [
+            TokenKind::LessThan,
+            TokenKind::LessEqual,
+            TokenKind::NotEqual,
+            TokenKind::GreaterThan,
+            TokenKind::GreaterEqual,
+            TokenKind::Equal,
+            TokenKind::Assign,
+            TokenKind::EOF,
+        ];
+        for kind in kinds {
+            assert_eq!(lexer.get_next_token().unwrap().kind, kind);
+        }
+    }
+
+    #[test]
+    fn test_unterminated_comment() {
+        let mut lexer = Lexer::new("3 + { oops".to_string());
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::Integer);
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::Plus);
+        assert_eq!(
+            lexer.get_next_token().unwrap_err().message,
+            "unterminated comment at line 1, column 5"
+        );
+    }
+
+    #[test]
+    fn test_line_comment() {
+        let mut lexer = Lexer::new("3 + 4 // this is ignored".to_string());
+        let kinds = [
+            TokenKind::Integer,
+            TokenKind::Plus,
+            TokenKind::Integer,
+            TokenKind::EOF,
+        ];
+        for kind in kinds {
+            assert_eq!(lexer.get_next_token().unwrap().kind, kind);
+        }
+    }
+
+    #[test]
+    fn test_line_comment_ends_at_newline() {
+        let mut lexer = Lexer::new("// first\n6 / 2".to_string());
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Integer);
+        assert_eq!(token.line, 2);
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::FloatDivide);
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::Integer);
+    }
+
+    #[test]
+    fn test_scientific_notation() {
+        let inputs = [("1.5e3", 1500.0), ("2E-2", 0.02), ("3e+1", 30.0)];
+        for (input, expected) in inputs {
+            let mut lexer = Lexer::new(input.to_string());
+            let token = lexer.get_next_token().unwrap();
+            assert_eq!(token.kind, TokenKind::Real);
+            assert_eq!(token.value, TokenValue::Real(expected));
+            assert_eq!(token.lexeme, input);
+            assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::EOF);
+        }
+    }
+
+    #[test]
+    fn test_malformed_exponent() {
+        for input in ["1.5e", "2E-", "3e+x"] {
+            let mut lexer = Lexer::new(input.to_string());
+            assert_eq!(
+                lexer.get_next_token().unwrap_err().message,
+                "malformed exponent in number at line 1, column 1"
+            );
+        }
+    }
+
+    #[test]
+    fn test_keywords_ignore_case() {
+        let mut lexer = Lexer::new("begin Div end".to_string());
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Begin);
+        assert_eq!(token.value, TokenValue::Str("begin".to_string()));
+        assert_eq!(
+            lexer.get_next_token().unwrap().kind,
+            TokenKind::IntegerDivide
+        );
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::End);
+    }
+
+    #[test]
+    fn test_backslash_is_integer_divide() {
+        let mut lexer = Lexer::new("7 \\ 2".to_string());
+        lexer.get_next_token().unwrap();
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::IntegerDivide);
+        assert_eq!(token.lexeme, "\\");
+    }
+
+    #[test]
+    fn test_unexpected_character() {
+        let mut lexer = Lexer::new("3 @ 4".to_string());
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::Integer);
+        assert_eq!(
+            lexer.get_next_token().unwrap_err().message,
+            "unexpected character '@' at line 1, column 3"
+        );
+    }
+
+    #[test]
+    fn test_integer_literal_overflow() {
+        let mut lexer = Lexer::new("1 + 999_999_999_999".to_string());
+        lexer.get_next_token().unwrap();
+        lexer.get_next_token().unwrap();
+        assert_eq!(
+            lexer.get_next_token().unwrap_err().message,
+            "integer literal 999999999999 is too large at line 1, column 5"
+        );
+
+        let mut lexer = Lexer::new("2147483647".to_string());
+        assert_eq!(
+            lexer.get_next_token().unwrap().value,
+            TokenValue::Int(i32::MAX)
+        );
+
+        // In bignum mode the literal is kept whole instead
+        let mut lexer = Lexer::new("999_999_999_999 2147483647".to_string());
+        lexer.bignum = true;
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::Integer);
+        assert_eq!(token.value.to_string(), "999999999999");
+        assert!(matches!(token.value, TokenValue::BigInt(_)));
+        assert_eq!(
+            lexer.get_next_token().unwrap().value,
+            TokenValue::Int(i32::MAX)
+        );
+    }
+
+    #[test]
+    fn test_numbers_are_ascii_digits() {
+        for bignum in [false, true] {
+            let mut lexer = Lexer::new("3 + ½".to_string());
+            lexer.bignum = bignum;
+            lexer.get_next_token().unwrap();
+            lexer.get_next_token().unwrap();
+            assert_eq!(
+                lexer.get_next_token().unwrap_err().message,
+                "unexpected character '½' at line 1, column 5"
+            );
+        }
+        let mut lexer = Lexer::new("1²".to_string());
+        assert_eq!(lexer.get_next_token().unwrap().value, TokenValue::Int(1));
+        assert!(lexer.get_next_token().is_err());
+    }
+
+    #[test]
+    fn test_string_literal() {
+        let mut lexer = Lexer::new("'it''s' + ''".to_string());
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::StringLiteral);
+        assert_eq!(token.value, TokenValue::Str("it's".to_string()));
+        assert_eq!(token.lexeme, "'it''s'");
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::Plus);
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.value, TokenValue::Str("".to_string()));
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let mut lexer = Lexer::new("'oops".to_string());
+        assert_eq!(
+            lexer.get_next_token().unwrap_err().message,
+            "unterminated string at line 1, column 1"
+        );
+    }
+
+    #[test]
+    fn test_long_input() {
+        // Rescanning the text for every character made this quadratic
+        let terms = 100_000;
+        let source = format!("{}π", "12 + ".repeat(terms));
+        let mut lexer = Lexer::new(source);
+        for _ in 0..terms {
+            assert_eq!(lexer.get_next_token().unwrap().value, TokenValue::Int(12));
+            assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::Plus);
+        }
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.value, TokenValue::Str("π".to_string()));
+        assert_eq!(token.lexeme, "π");
+        assert_eq!(token.column, terms * 5 + 1);
+        assert_eq!(lexer.get_next_token().unwrap().kind, TokenKind::EOF);
+    }
+
+    #[test]
+    fn test_tokens_iterator() {
+        let mut lexer = Lexer::new("3 + 5 * 2".to_string());
+        let kinds: Vec<TokenKind> = lexer.tokens().map(|token| token.unwrap().kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Integer,
+                TokenKind::Plus,
+                TokenKind::Integer,
+                TokenKind::Multiply,
+                TokenKind::Integer,
+            ]
+        );
+
+        let mut lexer = Lexer::new("1 @ 2".to_string());
+        let tokens: Vec<_> = lexer.tokens().collect();
+        assert_eq!(tokens.len(), 2);
+        assert!(tokens[1].is_err());
+    }
+
+    #[test]
+    fn test_char_literal() {
+        let mut lexer = Lexer::new("#65 #".to_string());
+        let token = lexer.get_next_token().unwrap();
+        assert_eq!(token.kind, TokenKind::CharLiteral);
+        assert_eq!(token.value, TokenValue::Int(65));
+        assert_eq!(token.lexeme, "#65");
+        assert_eq!(
+            lexer.get_next_token().unwrap_err().message,
+            "malformed character code at line 1, column 5"
+        );
+
+        let mut lexer = Lexer::new("#55296".to_string());
+        assert_eq!(
+            lexer.get_next_token().unwrap_err().message,
+            "invalid character code #55296 at line 1, column 1"
+        );
+    }
+
+    #[test]
+    fn test_brackets_and_ranges() {
+        let mut lexer = Lexer::new("a[1..5] 1.5".to_string());
+        let kinds: Vec<_> = lexer.tokens().map(|token| token.unwrap().kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Identifier,
+                TokenKind::LBracket,
+                TokenKind::Integer,
+                TokenKind::DotDot,
+                TokenKind::Integer,
+                TokenKind::RBracket,
+                TokenKind::Real,
+            ]
+        );
+    }
 }
@@ -0,0 +1,336 @@
+use std::{error::Error, fmt, str::FromStr};
+
+pub mod ast;
+pub mod formatter;
+pub mod interpreter;
+pub mod lexer;
+pub mod optimize;
+pub mod parser;
+pub mod printer;
+pub mod token;
+
+pub use ast::{AstNode, AstType};
+pub use interpreter::{Interpreter, InterpreterType};
+pub use lexer::Lexer;
+pub use parser::{Parser, ParserError};
+pub use token::{Token, TokenKind, TokenValue};
+
+#[derive(Debug)]
+pub enum CalcError {
+    Parse(ParserError),
+    Runtime(String),
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CalcError::Parse(e) => write!(f, "{}", e),
+            CalcError::Runtime(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for CalcError {}
+
+// Every token in `input` up to and including EOF, or the first lexer error.
+pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut lexer = Lexer::new(input.to_string());
+    let mut tokens = vec![];
+    loop {
+        let token = lexer.get_next_token().map_err(|e| e.message)?;
+        let done = token.kind == TokenKind::EOF;
+        tokens.push(token);
+        if done {
+            return Ok(tokens);
+        }
+    }
+}
+
+pub fn parse(source: &str) -> Result<AstNode, CalcError> {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut parser = Parser::new(&mut lexer);
+    parser.parse().map_err(CalcError::Parse)
+}
+
+pub fn evaluate(ast: &AstNode) -> Result<InterpreterType, CalcError> {
+    let mut interpreter = Interpreter::default();
+    interpreter.visit(ast).map_err(CalcError::Runtime)
+}
+
+// A parsed expression or program, so `"3 + 4".parse::<Expr>()?.eval()`
+// works. Each evaluation starts from a fresh interpreter.
+#[derive(Debug, Clone)]
+pub struct Expr {
+    ast: AstNode,
+}
+
+impl Expr {
+    pub fn eval(&self) -> Result<InterpreterType, String> {
+        evaluate(&self.ast).map_err(|e| e.to_string())
+    }
+
+    pub fn ast(&self) -> &AstNode {
+        &self.ast
+    }
+}
+
+impl FromStr for Expr {
+    type Err = CalcError;
+
+    fn from_str(source: &str) -> Result<Expr, CalcError> {
+        parse(source).map(|ast| Expr { ast })
+    }
+}
+
+pub fn eval(source: &str) -> Result<InterpreterType, CalcError> {
+    evaluate(&parse(source)?)
+}
+
+// Evaluates untrusted input: any failure, including a malformed number, a
+// truncated program or one nested too deeply to run, comes back as an
+// error message.
+pub fn try_eval(input: &str) -> Result<f64, String> {
+    eval(input).map(f64::from).map_err(|e| e.to_string())
+}
+
+pub fn eval_all(source: &str) -> Result<Vec<InterpreterType>, CalcError> {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut parser = Parser::new(&mut lexer);
+    let statements = parser.parse_statements().map_err(CalcError::Parse)?;
+    let mut interpreter = Interpreter::default();
+    let mut results = vec![];
+    for statement in &statements {
+        results.push(interpreter.visit(statement).map_err(CalcError::Runtime)?);
+    }
+    Ok(results)
+}
+
+// Every stage of running `input`: its tokens, the tree of each statement
+// and the value of each statement, one per line. Meant for diffing while
+// changing the grammar; a stage that fails shows its error and ends the
+// dump.
+pub fn debug_pipeline(input: &str) -> String {
+    let mut dump = String::from("tokens:\n");
+    match tokenize(input) {
+        Ok(tokens) => {
+            for token in tokens {
+                dump.push_str(&format!("  {}\n", token));
+            }
+        }
+        Err(e) => return dump + &format!("  error: {}\n", e),
+    }
+    dump.push_str("ast:\n");
+    let mut lexer = Lexer::new(input.to_string());
+    let mut parser = Parser::new(&mut lexer);
+    let statements = match parser.parse_statements() {
+        Ok(statements) => statements,
+        Err(e) => return dump + &format!("  error: {}\n", e.message),
+    };
+    for statement in &statements {
+        dump.push_str(&format!("  {}\n", statement));
+    }
+    dump.push_str("result:\n");
+    let mut interpreter = Interpreter::default();
+    for statement in &statements {
+        match interpreter.visit(statement) {
+            Ok(value) => dump.push_str(&format!("  {}\n", value)),
+            Err(e) => return dump + &format!("  error: {}\n", e),
+        }
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval() {
+        let result = eval("7 + 3 * 2").unwrap();
+        assert_eq!(result.from::<i32>(), 13);
+    }
+
+    #[test]
+    fn test_eval_real_arithmetic() {
+        let result = eval("7 / 2 + 0.25").unwrap();
+        assert_eq!(f64::from(result), 3.75);
+    }
+
+    #[test]
+    fn test_eval_program_with_assignments() {
+        let source = "PROGRAM P; VAR x, y : INTEGER; BEGIN x := 4; y := x * x END.";
+        assert!(eval(source).is_ok());
+        let results = eval_all(source).unwrap();
+        assert_eq!(results.last().unwrap().from::<i32>(), 16);
+    }
+
+    #[test]
+    fn test_reexported_pipeline() {
+        let mut lexer = Lexer::new("2 ** 5".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut interpreter = Interpreter::new(&mut parser);
+        assert_eq!(interpreter.interpret().unwrap(), 32.0);
+    }
+
+    #[test]
+    fn test_parse_once_evaluate_twice() {
+        let ast = parse("(3 + 5) * 2").unwrap();
+        assert_eq!(evaluate(&ast).unwrap().from::<i32>(), 16);
+        assert_eq!(evaluate(&ast).unwrap().from::<i32>(), 16);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("x := 3 + 4.5 { note }").unwrap();
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Identifier,
+                TokenKind::Assign,
+                TokenKind::Integer,
+                TokenKind::Plus,
+                TokenKind::Real,
+                TokenKind::EOF,
+            ]
+        );
+        assert_eq!(tokenize("").unwrap().len(), 1);
+        assert_eq!(
+            tokenize("1 @ 2").unwrap_err(),
+            "unexpected character '@' at line 1, column 3"
+        );
+    }
+
+    #[test]
+    fn test_expr_from_str() {
+        let expr: Expr = "(3 + 4) * 2".parse().unwrap();
+        assert_eq!(expr.ast().to_string(), "(* (+ 3 4) 2)");
+        assert!(matches!(expr.eval(), Ok(InterpreterType::Integer(14))));
+        assert!(matches!(expr.eval(), Ok(InterpreterType::Integer(14))));
+
+        let result: f64 = "7 / 2".parse::<Expr>().unwrap().eval().unwrap().from();
+        assert_eq!(result, 3.5);
+        assert!(matches!("3 +".parse::<Expr>(), Err(CalcError::Parse(_))));
+        assert_eq!(
+            "1 DIV 0".parse::<Expr>().unwrap().eval().unwrap_err(),
+            "Division by zero at line 1, column 3"
+        );
+    }
+
+    #[test]
+    fn test_debug_pipeline() {
+        assert_eq!(
+            debug_pipeline("3 + 5 * 2"),
+            "\
+tokens:
+  Token(Integer, Int(3))
+  Token(Plus, Str(\"+\"))
+  Token(Integer, Int(5))
+  Token(Multiply, Str(\"*\"))
+  Token(Integer, Int(2))
+  Token(EOF, Str(\"\"))
+ast:
+  (+ 3 (* 5 2))
+result:
+  13
+"
+        );
+        assert_eq!(
+            debug_pipeline("x := 2; x / 0"),
+            "\
+tokens:
+  Token(Identifier, Str(\"x\"))
+  Token(Assign, Str(\":=\"))
+  Token(Integer, Int(2))
+  Token(Semi, Str(\";\"))
+  Token(Identifier, Str(\"x\"))
+  Token(FloatDivide, Str(\"/\"))
+  Token(Integer, Int(0))
+  Token(EOF, Str(\"\"))
+ast:
+  (:= x 2)
+  (/ x 0)
+result:
+  2
+  error: Division by zero at line 1, column 11
+"
+        );
+        assert!(
+            debug_pipeline("3 +").ends_with("ast:\n  error: Invalid syntax at line 1, column 4\n")
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(matches!(parse("3 +"), Err(CalcError::Parse(_))));
+    }
+
+    #[test]
+    fn test_evaluate_error() {
+        let ast = parse("BEGIN a := b END.").unwrap();
+        assert!(matches!(evaluate(&ast), Err(CalcError::Runtime(_))));
+    }
+
+    #[test]
+    fn test_eval_all() {
+        let results = eval_all("x := 5; x + 1; x * 2").unwrap();
+        let results: Vec<i32> = results.iter().map(|value| value.from::<i32>()).collect();
+        assert_eq!(results, vec![5, 6, 10]);
+    }
+
+    #[test]
+    fn test_eval_all_with_program() {
+        let results = eval_all("BEGIN a := 2; b := a * 3 END.").unwrap();
+        let results: Vec<i32> = results.iter().map(|value| value.from::<i32>()).collect();
+        assert_eq!(results, vec![2, 6]);
+    }
+
+    #[test]
+    fn test_try_eval_rejects_garbage() {
+        let inputs = [
+            ":",
+            "99999999999999999999",
+            "VAR",
+            "1e",
+            "PROGRAM",
+            "BEGIN",
+            "\u{0}",
+        ];
+        for input in inputs {
+            assert!(try_eval(input).is_err(), "{:?}", input);
+        }
+        assert_eq!(try_eval("1.5e1 + 1"), Ok(16.0));
+    }
+
+    #[test]
+    fn test_try_eval_long_runs_of_operators() {
+        assert_eq!(
+            try_eval(&format!("1{}", "+1".repeat(100_000))),
+            Ok(100_001.0)
+        );
+        assert_eq!(
+            try_eval(&format!("(1{}) / 2", " * 1 - 1 + 1".repeat(10_000))),
+            Ok(0.5)
+        );
+    }
+
+    #[test]
+    fn test_try_eval_long_run_of_signs() {
+        assert_eq!(try_eval(&format!("{}1", "-".repeat(100_000))), Ok(1.0));
+        assert_eq!(
+            try_eval(&format!("2 * {}1", "- +".repeat(100_001))),
+            Ok(-2.0)
+        );
+    }
+
+    #[test]
+    fn test_keywords_ignore_case() {
+        let expected = eval_all("BEGIN a := 5; END.").unwrap();
+        for source in ["begin a := 5; end.", "Begin A := 5; End."] {
+            assert!(parse(source).is_ok(), "{}", source);
+            let results = eval_all(source).unwrap();
+            assert_eq!(results.len(), expected.len(), "{}", source);
+            assert_eq!(results[0].from::<i32>(), 5, "{}", source);
+        }
+    }
+}
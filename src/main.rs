@@ -1,36 +1,680 @@
+use std::collections::HashMap;
 use std::io::{stdin, stdout, Write};
 
-mod ast;
-mod interpreter;
-mod lexer;
-mod parser;
-mod token;
+use calculator::ast::AstNode;
+use calculator::formatter::FormatOptions;
+use calculator::interpreter::{Interpreter, InterpreterType};
+use calculator::lexer::Lexer;
+use calculator::parser::Parser;
+use calculator::token::TokenKind;
 
-use interpreter::Interpreter;
-use lexer::Lexer;
-use parser::Parser;
+#[derive(Default)]
+struct Cli {
+    ast: bool,
+    json: bool,
+    file: Option<String>,
+    bignum: bool,
+    verbose: bool,
+    expression: Option<String>,
+    variables: Vec<(String, InterpreterType)>,
+}
+
+impl Cli {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Cli, String> {
+        let mut cli = Cli::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--ast" => cli.ast = true,
+                "--json" => cli.json = true,
+                "--bignum" => cli.bignum = true,
+                "-v" | "--verbose" => cli.verbose = true,
+                "-e" => match args.next() {
+                    Some(expression) => cli.expression = Some(expression),
+                    None => return Err("Missing expression after -e".to_string()),
+                },
+                "--set" => match args.next() {
+                    Some(pair) => cli.variables.push(parse_variable(&pair)?),
+                    None => return Err("Missing name=value after --set".to_string()),
+                },
+                _ if !arg.starts_with('-') && cli.file.is_none() => cli.file = Some(arg),
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        Ok(cli)
+    }
+}
+
+fn parse_variable(pair: &str) -> Result<(String, InterpreterType), String> {
+    let invalid = || format!("Invalid --set value: {}", pair);
+    let (name, value) = pair.split_once('=').ok_or_else(invalid)?;
+    let mut chars = name.chars();
+    let valid_name =
+        chars.next().is_some_and(|c| c.is_alphabetic()) && chars.all(|c| c.is_alphanumeric());
+    if !valid_name {
+        return Err(invalid());
+    }
+    let value = match (value.parse::<i32>(), value.parse::<f64>()) {
+        (Ok(value), _) => InterpreterType::Integer(value),
+        (_, Ok(value)) => InterpreterType::Real(value),
+        _ => return Err(invalid()),
+    };
+    Ok((name.to_string(), value))
+}
+
+fn dump_ast(source: &str, cli: &Cli) -> Result<String, String> {
+    let mut lexer = Lexer::new(source.to_string());
+    lexer.bignum = cli.bignum;
+    let ast = Parser::new(&mut lexer).parse().map_err(|e| e.to_string())?;
+    if cli.json {
+        #[cfg(feature = "json")]
+        return serde_json::to_string_pretty(&ast).map_err(|e| e.to_string());
+        #[cfg(not(feature = "json"))]
+        return Err("Built without JSON support".to_string());
+    }
+    Ok(format!("{:#?}", ast))
+}
+
+// Parses a REPL line without running it and shows each statement's tree
+// as an S-expression, one per line.
+fn show_ast(source: &str, bignum: bool) -> Result<String, String> {
+    let mut lexer = Lexer::new(source.to_string());
+    lexer.bignum = bignum;
+    let mut parser = Parser::new(&mut lexer);
+    let statements = parser.parse_statements().map_err(|e| e.to_string())?;
+    Ok(statements
+        .iter()
+        .map(|statement| statement.to_string())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn configure(interpreter: &mut Interpreter, cli: &Cli) {
+    interpreter.bignum = cli.bignum;
+    for (name, value) in &cli.variables {
+        interpreter.set_variable(name, value.clone());
+    }
+}
+
+// Runs each top-level statement in turn, echoing the value of bare
+// expression statements (but not assignments) like an interactive session.
+// A program runs its declarations and then the statements of its main block.
+fn run_verbose(
+    source: &str,
+    options: &FormatOptions,
+    interpreter: &mut Interpreter,
+    out: &mut impl Write,
+) -> Result<(), String> {
+    let mut lexer = Lexer::new(source.to_string());
+    lexer.bignum = interpreter.bignum;
+    let mut parser = Parser::new(&mut lexer);
+    let statements = parser.parse_statements().map_err(|e| e.to_string())?;
+    for statement in statements {
+        let echo = matches!(
+            statement,
+            AstNode::BinaryOp(..)
+                | AstNode::UnaryOp(..)
+                | AstNode::Num(_)
+                | AstNode::Var(_)
+                | AstNode::Index(..)
+                | AstNode::IfExpr(..)
+                | AstNode::FunctionCall(..)
+        );
+        let value = interpreter.visit(&statement);
+        let output = interpreter.take_output();
+        write!(out, "{}", output).map_err(|e| e.to_string())?;
+        if echo && output.is_empty() {
+            writeln!(out, "{}", options.format(&value?)).map_err(|e| e.to_string())?;
+        } else {
+            value?;
+        }
+    }
+    Ok(())
+}
+
+// Runs a whole program, writing whatever it prints to `out`. Output printed
+// before a runtime error is still written.
+fn run_program(
+    source: &str,
+    interpreter: &mut Interpreter,
+    out: &mut impl Write,
+) -> Result<(), String> {
+    let mut lexer = Lexer::new(source.to_string());
+    lexer.bignum = interpreter.bignum;
+    let mut parser = Parser::new(&mut lexer);
+    let tree = parser.parse().map_err(|e| e.to_string())?;
+    let result = interpreter.visit(&tree);
+    write!(out, "{}", interpreter.take_output()).map_err(|e| e.to_string())?;
+    result.map(|_| ())
+}
+
+// Evaluates one line with a long-lived interpreter, so variables assigned
+// on earlier lines are still defined. Gives what the line printed, along
+// with the value of the last statement to show, which is empty when that
+// was a call, or the error the line stopped at. A line that succeeds
+// stores that value in `ans` for the next one.
+fn run_line(
+    input: &str,
+    options: &FormatOptions,
+    interpreter: &mut Interpreter,
+) -> (String, Result<String, String>) {
+    let mut lexer = Lexer::new(input.to_string());
+    lexer.bignum = interpreter.bignum;
+    let mut parser = Parser::new(&mut lexer);
+    let statements = match parser.parse_statements() {
+        Ok(statements) => statements,
+        Err(e) => return (String::new(), Err(e.to_string())),
+    };
+    let mut result = Ok(String::new());
+    let mut answer = None;
+    for statement in statements {
+        // Calls that print, like `writeln(x)`, show only what they printed
+        let printed = interpreter.output().len();
+        let call = matches!(statement, AstNode::ProcedureCall(..));
+        let function = matches!(statement, AstNode::FunctionCall(..));
+        match interpreter.visit(&statement) {
+            Ok(_) if call || (function && interpreter.output().len() > printed) => {
+                result = Ok(String::new());
+                answer = None;
+            }
+            Ok(value) => {
+                result = Ok(options.format(&value));
+                answer = Some(value);
+            }
+            Err(e) => {
+                result = Err(e);
+                answer = None;
+                break;
+            }
+        }
+    }
+    if let Some(value) = answer {
+        interpreter.set_variable("ans", value);
+    }
+    (interpreter.take_output(), result)
+}
+
+// Shows a line for -e and the REPL: what it printed and its value go to
+// stdout and an error goes to stderr. Tells whether the line succeeded.
+fn show_line(input: &str, options: &FormatOptions, interpreter: &mut Interpreter) -> bool {
+    let (output, result) = run_line(input, options, interpreter);
+    print!("{}", output);
+    match result {
+        // Printed output that ends its line needs no empty line after it
+        Ok(value) if value.is_empty() && output.ends_with('\n') => true,
+        Ok(value) => {
+            println!("{}", value);
+            true
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            false
+        }
+    }
+}
+
+// Whether the REPL has a whole input to run, or should keep reading lines.
+// Input is incomplete while brackets, BEGIN/END or REPEAT/UNTIL are still
+// open, or while a PROGRAM or BEGIN block is missing its final `.`. Input
+// the lexer rejects counts as complete so that the error gets shown.
+fn is_complete(input: &str) -> bool {
+    // Only the kinds of the tokens matter here, so big literals are let
+    // through whether or not bignum mode is on
+    let mut lexer = Lexer::new(input.to_string());
+    lexer.bignum = true;
+    let mut depth = 0;
+    let mut first = None;
+    let mut last = TokenKind::EOF;
+    loop {
+        let token = match lexer.get_next_token() {
+            Ok(token) => token,
+            Err(_) => return true,
+        };
+        match token.kind {
+            TokenKind::EOF => break,
+            TokenKind::LParen | TokenKind::LBracket | TokenKind::Begin | TokenKind::Repeat => {
+                depth += 1
+            }
+            TokenKind::RParen | TokenKind::RBracket | TokenKind::End | TokenKind::Until => {
+                depth -= 1
+            }
+            _ => {}
+        }
+        first.get_or_insert(token.kind.clone());
+        last = token.kind;
+    }
+    match first {
+        _ if depth > 0 => false,
+        Some(TokenKind::Program | TokenKind::Begin) => last == TokenKind::Dot,
+        _ => true,
+    }
+}
+
+const HELP: &str = "\
+:vars              list the defined variables
+:ast EXPR          show the parse tree of EXPR without evaluating it
+:load FILE         run a program file, keeping the variables it defines
+:clear             forget all variables
+:base N            print integers in base 2, 8, 10 or 16
+:precision N|off   digits after the decimal point
+:grouping on|off   group digits in thousands
+:sci on|off        scientific notation for reals
+:help              show this message
+exit               leave the calculator";
+
+fn format_scope(scope: &HashMap<String, InterpreterType>) -> String {
+    let mut names: Vec<&String> = scope.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| format!("{} = {}", name, scope[name]))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Runs a program file with the session's interpreter, so its globals are
+// still defined afterwards. Anything the program printed comes first,
+// followed by a line saying whether it ran.
+fn load_file(path: &str, interpreter: &mut Interpreter) -> Result<String, String> {
+    let source =
+        std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+    let mut printed = vec![];
+    let result = run_program(&source, interpreter, &mut printed);
+    let printed = String::from_utf8_lossy(&printed);
+    match result {
+        Ok(()) => Ok(format!("{}Loaded {}", printed, path)),
+        Err(e) => Err(format!("{}{}", printed, e)),
+    }
+}
+
+fn run_command(input: &str, options: &mut FormatOptions, interpreter: &mut Interpreter) -> String {
+    // Commands look like `:precision 2` or `:sci on`
+    let mut parts = input.trim_start_matches(':').split_whitespace();
+    let name = parts.next().unwrap_or("");
+    match name {
+        "vars" => return format_scope(&interpreter.global_scope),
+        "clear" => {
+            interpreter.clear_globals();
+            return "Variables cleared".to_string();
+        }
+        "help" => return HELP.to_string(),
+        "ast" => {
+            let source = input.trim_start_matches(':')[name.len()..].trim();
+            return show_ast(source, interpreter.bignum).unwrap_or_else(|e| e);
+        }
+        "load" => {
+            let path = input.trim_start_matches(':')[name.len()..].trim();
+            if !path.is_empty() {
+                return load_file(path, interpreter).unwrap_or_else(|e| e);
+            }
+        }
+        _ => {}
+    }
+    match parts.next() {
+        Some(value) => match options.set(name, value) {
+            Ok(()) => format!("{} set to {}", name, value),
+            Err(e) => e,
+        },
+        None => format!("Missing value for :{}", name),
+    }
+}
 
 fn main() {
+    let cli = match Cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let mut options = FormatOptions::default();
+    let mut interpreter = Interpreter::default();
+    configure(&mut interpreter, &cli);
+    if let Some(file) = &cli.file {
+        let source = match std::fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Could not read {}: {}", file, e);
+                std::process::exit(1);
+            }
+        };
+        let result = if cli.ast {
+            dump_ast(&source, &cli).map(|dump| println!("{}", dump))
+        } else if cli.verbose {
+            run_verbose(&source, &options, &mut interpreter, &mut stdout())
+        } else {
+            run_program(&source, &mut interpreter, &mut stdout())
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(expression) = &cli.expression {
+        if cli.verbose {
+            let result = run_verbose(expression, &options, &mut interpreter, &mut stdout());
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        } else if !show_line(expression, &options, &mut interpreter) {
+            std::process::exit(1);
+        }
+        return;
+    }
+    // Like a script, a session with a failed line ends with an error status
+    let mut failed = false;
+    let mut buffer = String::new();
     loop {
-        print!("calc> ");
+        print!("{}", if buffer.is_empty() { "calc> " } else { "...> " });
 
         // Flush stdout to ensure prompt is displayed immediately
         stdout().flush().unwrap();
 
         // Read user input as a String
-        let mut input = String::new();
-        stdin().read_line(&mut input).unwrap();
-        let input = input.trim();
-        if input == "exit" {
+        let mut line = String::new();
+        if stdin().read_line(&mut line).unwrap() == 0 {
             break;
         }
-        let mut lexer = Lexer::new(input.to_string());
-        let mut parser = Parser::new(&mut lexer);
-        let mut interpreter = Interpreter::new(&mut parser);
-        let result = interpreter.interpret();
-        match result {
-            Ok(value) => println!("{}", value),
-            Err(e) => println!("{}", e),
+        if buffer.is_empty() {
+            let input = line.trim();
+            if input == "exit" {
+                break;
+            }
+            if input.starts_with(':') {
+                println!("{}", run_command(input, &mut options, &mut interpreter));
+                continue;
+            }
+        }
+        // Keep reading until a multi-line program is finished
+        buffer.push_str(&line);
+        if !is_complete(&buffer) {
+            continue;
+        }
+        failed |= !show_line(buffer.trim(), &options, &mut interpreter);
+        buffer.clear();
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Everything a line shows, error included, as the REPL shows it.
+    fn eval_line(input: &str, options: &FormatOptions, interpreter: &mut Interpreter) -> String {
+        let (mut output, result) = run_line(input, options, interpreter);
+        let result = result.unwrap_or_else(|e| e);
+        if result.is_empty() && output.ends_with('\n') {
+            output.pop();
+        }
+        output + &result
+    }
+
+    fn eval(input: &str, options: &FormatOptions, cli: &Cli) -> String {
+        let mut interpreter = Interpreter::default();
+        configure(&mut interpreter, cli);
+        eval_line(input, options, &mut interpreter)
+    }
+
+    #[test]
+    fn test_eval_line_integer() {
+        assert_eq!(
+            eval("3 + 1", &FormatOptions::default(), &Cli::default()),
+            "4"
+        );
+    }
+
+    #[test]
+    fn test_eval_line_real() {
+        assert_eq!(
+            eval("7 / 2", &FormatOptions::default(), &Cli::default()),
+            "3.5"
+        );
+        assert_eq!(
+            eval("1.5 + 1.5", &FormatOptions::default(), &Cli::default()),
+            "3.0"
+        );
+    }
+
+    #[test]
+    fn test_eval_line_error() {
+        assert!(
+            eval("3 +", &FormatOptions::default(), &Cli::default()).starts_with("IntepreterError")
+        );
+    }
+
+    #[test]
+    fn test_format_commands() {
+        let mut options = FormatOptions::default();
+        let mut interpreter = Interpreter::default();
+        assert_eq!(
+            run_command(":precision 2", &mut options, &mut interpreter),
+            "precision set to 2"
+        );
+        assert_eq!(
+            run_command(":sci on", &mut options, &mut interpreter),
+            "sci set to on"
+        );
+        assert_eq!(eval("12345.678 * 10", &options, &Cli::default()), "1.23e5");
+        assert_eq!(
+            run_command(":base", &mut options, &mut interpreter),
+            "Missing value for :base"
+        );
+    }
+
+    #[test]
+    fn test_format_scope() {
+        let mut scope = HashMap::new();
+        assert_eq!(format_scope(&scope), "");
+        scope.insert("y".to_string(), InterpreterType::Real(0.5));
+        scope.insert("x".to_string(), InterpreterType::Integer(3));
+        scope.insert("done".to_string(), InterpreterType::Boolean(true));
+        assert_eq!(format_scope(&scope), "done = TRUE\nx = 3\ny = 0.5");
+    }
+
+    #[test]
+    fn test_vars_and_clear_commands() {
+        let mut options = FormatOptions::default();
+        let mut interpreter = Interpreter::default();
+        eval_line("b := 2; a := 1", &options, &mut interpreter);
+        assert_eq!(
+            run_command(":vars", &mut options, &mut interpreter),
+            "a = 1\nans = 1\nb = 2"
+        );
+        run_command(":clear", &mut options, &mut interpreter);
+        assert_eq!(run_command(":vars", &mut options, &mut interpreter), "");
+        assert!(run_command(":help", &mut options, &mut interpreter).contains(":vars"));
+    }
+
+    #[test]
+    fn test_show_ast() {
+        assert_eq!(
+            show_ast("3 + 5 * 2", false),
+            Ok("(+ 3 (* 5 2))".to_string())
+        );
+        assert_eq!(
+            show_ast("a := 2; a ** 2", false),
+            Ok("(:= a 2)\n(** a 2)".to_string())
+        );
+        assert!(show_ast("3 +", false).is_err());
+        assert!(show_ast("99999999999", false).is_err());
+        assert_eq!(show_ast("99999999999", true), Ok("99999999999".to_string()));
+    }
+
+    #[test]
+    fn test_ast_command_does_not_evaluate() {
+        let mut options = FormatOptions::default();
+        let mut interpreter = Interpreter::default();
+        assert_eq!(
+            run_command(":ast x := 1 / 0", &mut options, &mut interpreter),
+            "(:= x (/ 1 0))"
+        );
+        assert!(interpreter.global_scope.is_empty());
+        let error = run_command(":ast 2 *", &mut options, &mut interpreter);
+        assert_eq!(error, show_ast("2 *", false).unwrap_err());
+    }
+
+    #[test]
+    fn test_load_command() {
+        let path = std::env::temp_dir().join(format!("calculator-load-{}.pas", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let source = "PROGRAM P; VAR n : INTEGER; BEGIN n := 6 * 7; writeln('hi') END.";
+        std::fs::write(&path, source).unwrap();
+        let mut options = FormatOptions::default();
+        let mut interpreter = Interpreter::default();
+        eval_line("x := 1", &options, &mut interpreter);
+        let loaded = run_command(&format!(":load {}", path), &mut options, &mut interpreter);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, format!("hi\nLoaded {}", path));
+        assert_eq!(eval_line("x + n", &options, &mut interpreter), "43");
+
+        // A missing file leaves the session alone
+        let error = run_command(&format!(":load {}", path), &mut options, &mut interpreter);
+        assert!(
+            error.starts_with(&format!("Could not read {}: ", path)),
+            "{}",
+            error
+        );
+        assert_eq!(eval_line("x + n", &options, &mut interpreter), "43");
+        assert_eq!(
+            run_command(":load", &mut options, &mut interpreter),
+            "Missing value for :load"
+        );
+    }
+
+    #[test]
+    fn test_is_complete() {
+        let complete = [
+            "3 + 5 * 2",
+            "(1 + 2) * 3",
+            "1 + 2)",
+            "PROGRAM P;\nBEGIN\n  x := 1\nEND.",
+            "BEGIN IF a THEN BEGIN b := 1 END END.",
+            "REPEAT x := x + 1 UNTIL x > 3",
+            "'unterminated",
+            "",
+        ];
+        for input in complete {
+            assert!(is_complete(input), "{:?} should be complete", input);
+        }
+        let partial = [
+            "(1 + 2",
+            "sqrt((4)",
+            "PROGRAM P;",
+            "PROGRAM P;\nBEGIN\n  x := 1\nEND",
+            "BEGIN\n  x := 1;",
+            "BEGIN BEGIN END END",
+            "REPEAT x := x + 1;",
+            "BEGIN x := '(' END",
+        ];
+        for input in partial {
+            assert!(!is_complete(input), "{:?} should be partial", input);
         }
     }
+
+    #[test]
+    fn test_eval_line_bignum() {
+        let options = FormatOptions::default();
+        let bignum = Cli {
+            bignum: true,
+            ..Default::default()
+        };
+        assert_eq!(eval("65536 * 65536", &options, &bignum), "4294967296");
+        assert_eq!(
+            eval("65536 * 65536", &options, &Cli::default()),
+            "Integer overflow"
+        );
+
+        // Literals too large for an Integer are BigInts from the start
+        assert_eq!(
+            eval("99999999999999999999 + 1", &options, &bignum),
+            "100000000000000000000"
+        );
+        assert_eq!(eval("-2147483648 DIV 2", &options, &bignum), "-1073741824");
+        assert!(eval("99999999999999999999 + 1", &options, &Cli::default())
+            .contains("integer literal 99999999999999999999 is too large"));
+    }
+
+    #[test]
+    fn test_parse_variable() {
+        let (name, value) = parse_variable("x=21").unwrap();
+        assert_eq!(name, "x");
+        assert_eq!(value.to_string(), "21");
+        assert_eq!(parse_variable("rate=0.5").unwrap().1.to_string(), "0.5");
+        assert!(parse_variable("x").is_err());
+        assert!(parse_variable("1x=2").is_err());
+        assert!(parse_variable("x=abc").is_err());
+    }
+
+    #[test]
+    fn test_run_verbose_echoes_expressions_only() {
+        let options = FormatOptions::default();
+        let mut out = vec![];
+        let result = run_verbose(
+            "3 + 4; x := 5; writeln('x'); x * 2; IF x > 1 THEN 1 ELSE 2",
+            &options,
+            &mut Interpreter::default(),
+            &mut out,
+        );
+        assert!(result.is_ok());
+        assert_eq!(String::from_utf8(out).unwrap(), "7\nx\n10\n1\n");
+
+        // A program's statements run too, and its globals stay defined
+        let source = "PROGRAM P; VAR a : ARRAY[1..2] OF INTEGER;
+            PROCEDURE Show; BEGIN writeln(a[2] * 2) END;
+            BEGIN a[2] := 3; Show END.";
+        let mut interpreter = Interpreter::default();
+        let mut out = vec![];
+        assert!(run_verbose(source, &options, &mut interpreter, &mut out).is_ok());
+        assert!(run_verbose("a[2]", &options, &mut interpreter, &mut out).is_ok());
+        assert_eq!(String::from_utf8(out).unwrap(), "6\n3\n");
+    }
+
+    #[test]
+    fn test_eval_line_shows_printed_output() {
+        let options = FormatOptions::default();
+        let mut interpreter = Interpreter::default();
+        assert_eq!(eval_line("writeln(1 + 2)", &options, &mut interpreter), "3");
+        assert_eq!(
+            eval_line("write('x'); 2 * 3", &options, &mut interpreter),
+            "x6"
+        );
+        assert_eq!(interpreter.output(), "");
+    }
+
+    #[test]
+    fn test_run_program() {
+        let source = "PROGRAM P; VAR x, y : INTEGER; BEGIN x := 5; writeln(x); y := x DIV 0 END.";
+        let mut interpreter = Interpreter::default();
+        let mut out = vec![];
+        let result = run_program(source, &mut interpreter, &mut out);
+        assert!(result.unwrap_err().starts_with("Division by zero"));
+        assert_eq!(String::from_utf8(out).unwrap(), "5\n");
+        assert_eq!(interpreter.global_scope["x"].to_string(), "5");
+        assert_eq!(interpreter.global_scope["y"].to_string(), "0");
+    }
+
+    #[test]
+    fn test_ans_holds_the_last_result() {
+        let options = FormatOptions::default();
+        let mut interpreter = Interpreter::default();
+        assert_eq!(eval_line("3 + 4", &options, &mut interpreter), "7");
+        assert_eq!(eval_line("ans * 2", &options, &mut interpreter), "14");
+        assert!(eval_line("ans / 0", &options, &mut interpreter).starts_with("Division by zero"));
+        assert!(eval_line("ans +", &options, &mut interpreter).starts_with("IntepreterError"));
+        assert_eq!(eval_line("ans", &options, &mut interpreter), "14");
+    }
+
+    #[test]
+    fn test_eval_line_keeps_variables_between_lines() {
+        let options = FormatOptions::default();
+        let mut interpreter = Interpreter::default();
+        assert_eq!(eval_line("a := 5", &options, &mut interpreter), "5");
+        assert_eq!(eval_line("a + 1", &options, &mut interpreter), "6");
+        assert!(eval_line("a +", &options, &mut interpreter).starts_with("IntepreterError"));
+        assert_eq!(eval_line("a * 2", &options, &mut interpreter), "10");
+    }
 }
@@ -0,0 +1,115 @@
+use std::mem;
+
+use crate::ast::{AstNode, AstType};
+use crate::interpreter::{Interpreter, InterpreterType};
+
+// Replaces operators whose operands are all literals with the literal they
+// evaluate to, so `3 + 4 * 2` becomes `11`. Anything that reads a variable or
+// calls a function is left for the interpreter.
+pub fn fold_constants(mut node: AstNode) -> AstNode {
+    fold_children(&mut node);
+    evaluate(node)
+}
+
+// Folds each child of `node` in place. Nodes can't be taken apart by value
+// since they drop their children themselves.
+fn fold_children(node: &mut AstNode) {
+    let fold = |node: &mut AstNode| *node = fold_constants(mem::take(node));
+    let fold_all = |nodes: &mut Vec<AstNode>| nodes.iter_mut().for_each(fold);
+    match node {
+        AstNode::BinaryOp(left, right, _)
+        | AstNode::Assign(left, right, _)
+        | AstNode::While(left, right) => {
+            fold(left);
+            fold(right);
+        }
+        AstNode::UnaryOp(child, _)
+        | AstNode::Index(_, child)
+        | AstNode::Program(_, child)
+        | AstNode::ConstDecl(_, child)
+        | AstNode::ProcedureDecl(_, _, child) => fold(child),
+        AstNode::Compound(nodes)
+        | AstNode::FunctionCall(_, nodes)
+        | AstNode::ProcedureCall(_, nodes) => fold_all(nodes),
+        AstNode::Block(nodes, child) | AstNode::Repeat(nodes, child) => {
+            fold_all(nodes);
+            fold(child);
+        }
+        AstNode::Formatted(first, second, third)
+        | AstNode::If(first, second, third)
+        | AstNode::IfExpr(first, second, third) => {
+            fold(first);
+            fold(second);
+            fold(third);
+        }
+        AstNode::For(_, start, end, _, body) => {
+            fold(start);
+            fold(end);
+            fold(body);
+        }
+        _ => {}
+    }
+}
+
+// Runs an operator whose operands are literals through the interpreter, so
+// folding can't disagree with evaluation. Errors such as division by zero
+// stay in the tree to be reported when the program runs. Evaluating in
+// bignum mode means a result that only fits a BigInt is never folded, so
+// the answer is the same with or without bignum.
+fn evaluate(node: AstNode) -> AstNode {
+    let literal = |node: &AstNode| matches!(node, AstNode::Num(_));
+    let foldable = match &node {
+        AstNode::BinaryOp(left, right, _) => literal(left) && literal(right),
+        AstNode::UnaryOp(operand, _) => literal(operand),
+        _ => false,
+    };
+    if !foldable {
+        return node;
+    }
+    let mut interpreter = Interpreter::default();
+    interpreter.bignum = true;
+    match interpreter.visit(&node) {
+        Ok(InterpreterType::Integer(value)) => AstNode::Num(AstType::Integer(value)),
+        Ok(InterpreterType::Real(value)) => AstNode::Num(AstType::Real(value)),
+        Ok(InterpreterType::Boolean(value)) => AstNode::Num(AstType::Boolean(value)),
+        Ok(InterpreterType::Str(value)) => AstNode::Num(AstType::Str(value)),
+        Ok(InterpreterType::Char(value)) => AstNode::Num(AstType::Char(value)),
+        Ok(InterpreterType::BigInt(_) | InterpreterType::Array(..)) | Err(_) => node,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn fold(source: &str) -> String {
+        fold_constants(parse(source).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_folds_literal_arithmetic() {
+        let node = fold_constants(parse("3 + 4*2").unwrap());
+        assert!(matches!(node, AstNode::Num(AstType::Integer(11))));
+        assert_eq!(fold("2 ** -2"), "0.25");
+        assert_eq!(fold("-(1 + 2) < 0 AND TRUE"), "TRUE");
+        assert_eq!(fold("'a' + 'b'"), "'ab'");
+    }
+
+    #[test]
+    fn test_leaves_variables_alone() {
+        assert_eq!(fold("x + 1"), "(+ x 1)");
+        assert_eq!(fold("x * (2 + 3) - sqrt(1 + 3)"), "(- (* x 5) (sqrt 4))");
+        assert_eq!(
+            fold("BEGIN WHILE i < 2 * 5 DO i := i + 10 DIV 2 END."),
+            "(begin (while (< i 10) (:= i (+ i 5))))"
+        );
+    }
+
+    #[test]
+    fn test_leaves_errors_and_big_results_alone() {
+        assert_eq!(fold("1 / 0"), "(/ 1 0)");
+        assert_eq!(fold("2 ** 40"), "(** 2 40)");
+        assert_eq!(fold("2147483647 + 1"), "(+ 2147483647 1)");
+    }
+}
@@ -1,12 +1,67 @@
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, mem};
 
 use crate::ast::{AstNode, AstType};
-use crate::lexer::Lexer;
-use crate::token::{Token, TokenKind};
+use crate::lexer::{Lexer, LexerError};
+use crate::token::{Token, TokenKind, TokenValue};
+
+// How deeply a program may nest: each parenthesis, exponent and NOT is one
+// level, as is each statement or procedure inside another. Parsing one of
+// these recurses, so without a limit a long run of `(((` would crash the
+// program instead of failing to parse. A flat run like `1 + 2 + 3` is read
+// in a loop and doesn't count, however long it is.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+const COMPARISON_OPERATORS: [TokenKind; 6] = [
+    TokenKind::Equal,
+    TokenKind::NotEqual,
+    TokenKind::LessThan,
+    TokenKind::LessEqual,
+    TokenKind::GreaterThan,
+    TokenKind::GreaterEqual,
+];
+
+const MULTIPLYING_OPERATORS: [TokenKind; 6] = [
+    TokenKind::Multiply,
+    TokenKind::FloatDivide,
+    TokenKind::IntegerDivide,
+    TokenKind::Modulo,
+    TokenKind::Shl,
+    TokenKind::Shr,
+];
+
+// Where the parser reads its tokens from.
+enum TokenSource<'a> {
+    Lexer(&'a mut Lexer),
+    // Tokens collected up front and the index of the next one
+    Tokens(Vec<Token>, usize),
+}
+
+impl TokenSource<'_> {
+    fn next_token(&mut self) -> Result<Token, LexerError> {
+        match self {
+            TokenSource::Lexer(lexer) => lexer.get_next_token(),
+            // Running out of tokens counts as the end of input
+            TokenSource::Tokens(tokens, index) => {
+                let token = tokens
+                    .get(*index)
+                    .cloned()
+                    .unwrap_or_else(|| Token::new(TokenKind::EOF, TokenValue::Str("".to_string())));
+                *index += 1;
+                Ok(token)
+            }
+        }
+    }
+}
 
 pub struct Parser<'a> {
-    pub lexer: &'a mut Lexer,
+    source: TokenSource<'a>,
     pub current_token: Option<Token>,
+    // The token after `current_token`, once `peek_token` has read it
+    next_token: Option<Token>,
+    // A lexer error on the very first token, reported when parsing starts
+    error: Option<ParserError>,
+    pub max_depth: usize,
+    depth: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -22,70 +77,153 @@ impl fmt::Display for ParserError {
 
 impl Error for ParserError {}
 
+impl From<LexerError> for ParserError {
+    fn from(error: LexerError) -> ParserError {
+        ParserError {
+            message: error.message,
+        }
+    }
+}
+
 impl<'a> Parser<'a> {
     pub fn new(lexer: &'a mut Lexer) -> Parser<'a> {
-        let l = lexer;
-        let token = l.get_next_token();
+        Parser::with_source(TokenSource::Lexer(lexer))
+    }
+
+    // Parses tokens that were already lexed, or built by hand. A missing
+    // final EOF token is supplied.
+    pub fn from_tokens(tokens: Vec<Token>) -> Parser<'a> {
+        Parser::with_source(TokenSource::Tokens(tokens, 0))
+    }
+
+    fn with_source(mut source: TokenSource<'a>) -> Parser<'a> {
+        let (token, error) = match source.next_token() {
+            Ok(token) => (Some(token), None),
+            Err(error) => (None, Some(error.into())),
+        };
         Parser {
-            lexer: l,
+            source,
             current_token: token,
+            next_token: None,
+            error,
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
         }
     }
 
-    fn eat(&mut self, kind: TokenKind) -> Result<(), ParserError> {
-        if let Some(token) = self.current_token.clone() {
-            if token.kind == kind {
-                self.current_token = self.lexer.get_next_token();
-                Ok(())
-            } else {
-                Err(ParserError {
-                    message: "Invalid syntax".to_string(),
-                })
+    // Moves past the current token, which must be of the given kind, and
+    // hands it back so callers can keep it without cloning.
+    fn eat(&mut self, kind: TokenKind) -> Result<Token, ParserError> {
+        match &self.current_token {
+            Some(token) if token.kind == kind => self.advance(),
+            Some(token) => {
+                Err(self.error(&format!("expected {} but found {}", kind, describe(token))))
             }
-        } else {
-            Err(ParserError {
-                message: "Unexpected end of input".to_string(),
-            })
+            None => Err(unexpected_end()),
+        }
+    }
+
+    fn advance(&mut self) -> Result<Token, ParserError> {
+        let next = match self.next_token.take() {
+            Some(next) => next,
+            None => self.source.next_token()?,
+        };
+        self.current_token.replace(next).ok_or_else(unexpected_end)
+    }
+
+    // Whether the current token is one of `kinds`. Looking is free, so the
+    // grammar checks with this and only takes a token it is going to keep.
+    fn at(&self, kinds: &[TokenKind]) -> bool {
+        matches!(&self.current_token, Some(token) if kinds.contains(&token.kind))
+    }
+
+    fn kind(&self) -> Result<TokenKind, ParserError> {
+        match &self.current_token {
+            Some(token) => Ok(token.kind.clone()),
+            None => Err(unexpected_end()),
+        }
+    }
+
+    // The token after the current one, read from the lexer the first time
+    // and then kept until `eat` moves past it.
+    pub fn peek_token(&mut self) -> Result<Token, ParserError> {
+        if let Some(token) = &self.next_token {
+            return Ok(token.clone());
+        }
+        let token = self.source.next_token()?;
+        self.next_token = Some(token.clone());
+        Ok(token)
+    }
+
+    fn error(&self, message: &str) -> ParserError {
+        // Point at the token the parser was looking at when it gave up
+        match &self.current_token {
+            Some(token) => ParserError {
+                message: format!(
+                    "{} at line {}, column {}",
+                    message, token.line, token.column
+                ),
+            },
+            None => ParserError {
+                message: message.to_string(),
+            },
         }
     }
 
     fn program(&mut self) -> Result<AstNode, ParserError> {
         // program: PROGRAM variable SEMI block DOT
         // | BEGIN statement_list END
-        let node: AstNode;
-        match self.current_token.clone() {
-            Some(token) => match token.kind {
-                TokenKind::Program => {
-                    self.eat(TokenKind::Program)?;
-                    let var_node = match self.variable()? {
-                        AstNode::Var(var_node) => var_node.value.parse::<String>(),
-                        _ => {
-                            return Err(ParserError {
-                                message: "Invalid syntax".to_string(),
-                            });
-                        }
-                    };
-                    self.eat(TokenKind::Semi)?;
-                    let block_node = self.block()?;
-                    node = AstNode::Program(var_node, Box::new(block_node));
-                    self.eat(TokenKind::Dot)?;
-                }
-                TokenKind::Begin => {
-                    node = self.compound_statement()?;
-                    self.eat(TokenKind::Dot)?;
-                }
-                _ => node = self.expr()?,
-            },
-            None => {
-                return Err(ParserError {
-                    message: "Unexpected end of input".to_string(),
-                })
+        let node = match self.kind()? {
+            TokenKind::Program => {
+                self.eat(TokenKind::Program)?;
+                let var_node = match &self.variable()? {
+                    AstNode::Var(var_node) => var_node.value.parse::<String>(),
+                    _ => {
+                        return Err(self.error("Invalid syntax"));
+                    }
+                };
+                self.eat(TokenKind::Semi)?;
+                let block_node = self.block()?;
+                self.final_dot()?;
+                AstNode::Program(var_node, Box::new(block_node))
             }
+            TokenKind::Begin => {
+                let node = self.compound_statement()?;
+                self.final_dot()?;
+                node
+            }
+            _ => self.expr()?,
         };
-        self.eat(TokenKind::EOF)?;
+        self.end_of_input()?;
         Ok(node)
     }
 
+    // The `.` after the END of a program. An END found here has no BEGIN
+    // left to close.
+    fn final_dot(&mut self) -> Result<Token, ParserError> {
+        match &self.current_token {
+            Some(token) if token.kind == TokenKind::End => {
+                Err(self.error(&format!("unexpected {}", token.lexeme)))
+            }
+            _ => self.eat(TokenKind::Dot),
+        }
+    }
+
+    // Every way of parsing a whole input finishes here, so anything left
+    // over is reported the same way.
+    fn end_of_input(&self) -> Result<(), ParserError> {
+        match &self.current_token {
+            Some(token) if token.kind == TokenKind::End => {
+                Err(self.error(&format!("unexpected {}", token.lexeme)))
+            }
+            Some(token) if token.kind != TokenKind::EOF => Err(self.error(&format!(
+                "Invalid syntax: unexpected trailing token {}",
+                describe(token)
+            ))),
+            _ => Ok(()),
+        }
+    }
+
     fn block(&mut self) -> Result<AstNode, ParserError> {
         // block : declarations compound_statement
         let declarations = self.declarations()?;
@@ -94,40 +232,94 @@ impl<'a> Parser<'a> {
     }
 
     fn declarations(&mut self) -> Result<Vec<AstNode>, ParserError> {
-        // declarations : VAR (variable_declaration SEMI)+
-        // | empty
+        // declarations : (CONST (ID EQ expr SEMI)+
+        // | VAR (variable_declaration SEMI)+
+        // | procedure_declaration)*
         let mut declarations = vec![];
-        if let Some(token) = self.current_token.clone() {
-            if token.kind == TokenKind::Var {
-                self.eat(TokenKind::Var)?;
-                while let Some(token) = self.current_token.clone() {
-                    if token.kind == TokenKind::Identifier {
-                        declarations.append(&mut self.variable_declaration()?);
-                        self.eat(TokenKind::Semi)?;
-                    } else {
-                        break;
-                    }
+        loop {
+            match self.kind()? {
+                TokenKind::Const => self.constant_section(&mut declarations)?,
+                TokenKind::Var => self.variable_section(&mut declarations)?,
+                TokenKind::Procedure => {
+                    declarations.push(self.nested(Self::procedure_declaration)?)
                 }
+                _ => break,
             }
         }
         Ok(declarations)
     }
 
+    // The sections are read apart from `declarations`, which every nested
+    // procedure passes through, to keep its stack frame small.
+    fn constant_section(&mut self, declarations: &mut Vec<AstNode>) -> Result<(), ParserError> {
+        self.eat(TokenKind::Const)?;
+        while self.at(&[TokenKind::Identifier]) {
+            declarations.push(self.constant_declaration()?);
+            self.eat(TokenKind::Semi)?;
+        }
+        Ok(())
+    }
+
+    fn variable_section(&mut self, declarations: &mut Vec<AstNode>) -> Result<(), ParserError> {
+        self.eat(TokenKind::Var)?;
+        while self.at(&[TokenKind::Identifier]) {
+            declarations.append(&mut self.variable_declaration()?);
+            self.eat(TokenKind::Semi)?;
+        }
+        Ok(())
+    }
+
+    fn constant_declaration(&mut self) -> Result<AstNode, ParserError> {
+        // constant_declaration : ID EQ expr
+        let name = match &self.variable()? {
+            AstNode::Var(token) => token.value.parse::<String>(),
+            _ => return Err(self.error("Invalid syntax")),
+        };
+        self.eat(TokenKind::Equal)?;
+        Ok(AstNode::ConstDecl(name, Box::new(self.expr()?)))
+    }
+
+    fn procedure_declaration(&mut self) -> Result<AstNode, ParserError> {
+        // procedure_declaration : PROCEDURE ID
+        // (LPAREN variable_declaration (SEMI variable_declaration)* RPAREN)? SEMI block SEMI
+        let (name, params) = self.procedure_heading()?;
+        self.block().and_then(|block| {
+            self.eat(TokenKind::Semi)?;
+            Ok(AstNode::ProcedureDecl(name, params, Box::new(block)))
+        })
+    }
+
+    fn procedure_heading(&mut self) -> Result<(String, Vec<AstNode>), ParserError> {
+        self.eat(TokenKind::Procedure)?;
+        let name = match &self.variable()? {
+            AstNode::Var(token) => token.value.parse::<String>(),
+            _ => return Err(self.error("Invalid syntax")),
+        };
+        let mut params = vec![];
+        if self.at(&[TokenKind::LParen]) {
+            self.eat(TokenKind::LParen)?;
+            params.append(&mut self.variable_declaration()?);
+            while self.at(&[TokenKind::Semi]) {
+                self.eat(TokenKind::Semi)?;
+                params.append(&mut self.variable_declaration()?);
+            }
+            self.eat(TokenKind::RParen)?;
+        }
+        self.eat(TokenKind::Semi)?;
+        Ok((name, params))
+    }
+
     fn variable_declaration(&mut self) -> Result<Vec<AstNode>, ParserError> {
         // variable_declaration : ID (COMMA ID)* COLON type_spec
-        let mut var_nodes = vec![AstNode::Var(self.current_token.clone().unwrap())];
-        self.eat(TokenKind::Identifier)?;
-        while let Some(token) = self.current_token.clone() {
-            match token.kind {
-                TokenKind::Comma => {
-                    self.eat(TokenKind::Comma)?;
-                    var_nodes.push(AstNode::Var(self.current_token.clone().unwrap()));
-                    self.eat(TokenKind::Identifier)?;
-                }
-                _ => break,
-            }
+        let mut var_nodes = vec![AstNode::Var(self.eat(TokenKind::Identifier)?)];
+        while self.at(&[TokenKind::Comma]) {
+            self.eat(TokenKind::Comma)?;
+            var_nodes.push(AstNode::Var(self.eat(TokenKind::Identifier)?));
         }
         self.eat(TokenKind::Colon)?;
+        if self.at(&[TokenKind::Array]) {
+            return self.array_declaration(var_nodes);
+        }
         let type_node = self.type_spec()?;
         let mut declarations = vec![];
         for var_node in var_nodes {
@@ -137,32 +329,81 @@ impl<'a> Parser<'a> {
         Ok(declarations)
     }
 
+    fn array_declaration(&mut self, var_nodes: Vec<AstNode>) -> Result<Vec<AstNode>, ParserError> {
+        // array_type : ARRAY LBRACKET bound DOTDOT bound RBRACKET OF type_spec
+        self.eat(TokenKind::Array)?;
+        self.eat(TokenKind::LBracket)?;
+        let low = self.bound()?;
+        self.eat(TokenKind::DotDot)?;
+        let high = self.bound()?;
+        if low > high {
+            return Err(self.error(&format!("empty array range {}..{}", low, high)));
+        }
+        self.eat(TokenKind::RBracket)?;
+        self.eat(TokenKind::Of)?;
+        let type_node = self.type_spec()?;
+        Ok(var_nodes
+            .into_iter()
+            .map(|var_node| {
+                AstNode::ArrayDecl(Box::new(var_node), low, high, Box::new(type_node.clone()))
+            })
+            .collect())
+    }
+
+    fn bound(&mut self) -> Result<i32, ParserError> {
+        // bound : MINUS? INTEGER
+        let negative = self.at(&[TokenKind::Minus]);
+        if negative {
+            self.eat(TokenKind::Minus)?;
+        }
+        match &self.current_token {
+            Some(Token {
+                kind: TokenKind::Integer,
+                value: TokenValue::Int(value),
+                ..
+            }) => {
+                let value = *value;
+                self.eat(TokenKind::Integer)?;
+                Ok(if negative { -value } else { value })
+            }
+            _ => Err(self.error("array bounds must be integer literals")),
+        }
+    }
+
     fn type_spec(&mut self) -> Result<AstNode, ParserError> {
         // type_spec : INTEGER
         // | REAL
-        let token = self.current_token.clone().unwrap();
-        match token.kind {
-            TokenKind::Integer => {
-                self.eat(TokenKind::Integer)?;
-                Ok(AstNode::Type(token))
-            }
-            TokenKind::Real => {
-                self.eat(TokenKind::Real)?;
-                Ok(AstNode::Type(token))
-            }
-            _ => Err(ParserError {
-                message: "Invalid syntax".to_string(),
-            }),
+        // | BOOLEAN
+        // | CHAR
+        let types = [
+            TokenKind::Boolean,
+            TokenKind::Char,
+            TokenKind::Integer,
+            TokenKind::Real,
+        ];
+        if self.at(&types) {
+            Ok(AstNode::Type(self.advance()?))
+        } else {
+            Err(self.error("Invalid syntax"))
         }
     }
 
     fn compound_statement(&mut self) -> Result<AstNode, ParserError> {
         // compound_statement: BEGIN statement_list END
-        self.eat(TokenKind::Begin)?;
+        let begin = self.eat(TokenKind::Begin)?;
         let nodes = self.statement_list()?;
+        // Running into the end of the program, or the `.` that ends it,
+        // means an END is missing; anything else is a mistake in between
+        if self.at(&[TokenKind::Dot, TokenKind::EOF]) {
+            return Err(ParserError {
+                message: format!(
+                    "unmatched BEGIN at line {}, column {}",
+                    begin.line, begin.column
+                ),
+            });
+        }
         self.eat(TokenKind::End)?;
-        let root = AstNode::Compound(nodes);
-        Ok(root)
+        Ok(AstNode::Compound(nodes))
     }
 
     fn statement_list(&mut self) -> Result<Vec<AstNode>, ParserError> {
@@ -170,30 +411,112 @@ impl<'a> Parser<'a> {
         // | statement SEMI statement_list
         let node = self.statement()?;
         let mut results = vec![node];
-        while let Some(token) = self.current_token.clone() {
-            if token.kind == TokenKind::Semi {
-                self.eat(TokenKind::Semi)?;
-                let other_node = self.statement()?;
-                results.push(other_node);
-            } else {
-                break;
-            }
+        while self.at(&[TokenKind::Semi]) {
+            self.eat(TokenKind::Semi)?;
+            results.push(self.statement()?);
         }
+        self.check_missing_semicolon()?;
         Ok(results)
     }
 
+    fn check_missing_semicolon(&self) -> Result<(), ParserError> {
+        // A statement directly followed by the start of another one
+        let statement_starts = [
+            TokenKind::Identifier,
+            TokenKind::Begin,
+            TokenKind::If,
+            TokenKind::While,
+            TokenKind::Repeat,
+            TokenKind::For,
+        ];
+        if self.at(&statement_starts) {
+            return Err(self.error("missing ';' between statements"));
+        }
+        Ok(())
+    }
+
     fn statement(&mut self) -> Result<AstNode, ParserError> {
-        if let Some(token) = self.current_token.clone() {
-            match token.kind {
-                TokenKind::Begin => self.compound_statement(),
-                TokenKind::Identifier => self.assignment_statement(),
-                _ => self.empty(),
-            }
+        match self.kind()? {
+            TokenKind::Begin => self.nested(Self::compound_statement),
+            TokenKind::Identifier => self.identifier_statement(),
+            TokenKind::If => self.nested(Self::if_statement),
+            TokenKind::While => self.nested(Self::while_statement),
+            TokenKind::Repeat => self.nested(Self::repeat_statement),
+            TokenKind::For => self.nested(Self::for_statement),
+            _ => self.empty(),
+        }
+    }
+
+    fn identifier_statement(&mut self) -> Result<AstNode, ParserError> {
+        // An assignment to a variable or an element, or a procedure call
+        let name = self.eat(TokenKind::Identifier)?;
+        if self.at(&[TokenKind::LBracket]) {
+            let element = self.element(name)?;
+            return self.assignment_statement(element);
+        }
+        if self.at(&[TokenKind::Assign]) {
+            return self.assignment_statement(AstNode::Var(name));
+        }
+        self.procedure_call_statement(name)
+    }
+
+    fn if_statement(&mut self) -> Result<AstNode, ParserError> {
+        // if_statement : IF expr THEN statement (ELSE statement)?
+        self.eat(TokenKind::If)?;
+        let condition = self.expr()?;
+        self.eat(TokenKind::Then)?;
+        let then_branch = self.statement()?;
+        let else_branch = if self.at(&[TokenKind::Else]) {
+            self.eat(TokenKind::Else)?;
+            self.statement()?
         } else {
-            Err(ParserError {
-                message: "Unexpected end of input".to_string(),
-            })
+            AstNode::NoOp
+        };
+        Ok(AstNode::If(
+            Box::new(condition),
+            Box::new(then_branch),
+            Box::new(else_branch),
+        ))
+    }
+
+    fn while_statement(&mut self) -> Result<AstNode, ParserError> {
+        // while_statement : WHILE expr DO statement
+        self.eat(TokenKind::While)?;
+        let condition = self.expr()?;
+        self.eat(TokenKind::Do)?;
+        let body = self.statement()?;
+        Ok(AstNode::While(Box::new(condition), Box::new(body)))
+    }
+
+    fn repeat_statement(&mut self) -> Result<AstNode, ParserError> {
+        // repeat_statement : REPEAT statement_list UNTIL expr
+        self.eat(TokenKind::Repeat)?;
+        let body = self.statement_list()?;
+        self.eat(TokenKind::Until)?;
+        let condition = self.expr()?;
+        Ok(AstNode::Repeat(body, Box::new(condition)))
+    }
+
+    fn for_statement(&mut self) -> Result<AstNode, ParserError> {
+        // for_statement : FOR variable ASSIGN expr (TO | DOWNTO) expr DO statement
+        self.eat(TokenKind::For)?;
+        let variable = self.variable()?;
+        self.eat(TokenKind::Assign)?;
+        let start = self.expr()?;
+        if !self.at(&[TokenKind::To, TokenKind::Downto]) {
+            return Err(self.error("expected TO or DOWNTO"));
         }
+        let direction = self.advance()?.kind;
+        let end = self.expr()?;
+        self.eat(TokenKind::Do)?;
+        let body = self.statement()?;
+        Ok(AstNode::For(
+            Box::new(variable),
+            Box::new(start),
+            Box::new(end),
+            direction,
+            Box::new(body),
+        ))
     }
 
     fn empty(&mut self) -> Result<AstNode, ParserError> {
@@ -201,126 +524,417 @@ impl<'a> Parser<'a> {
         Ok(AstNode::NoOp)
     }
 
-    fn assignment_statement(&mut self) -> Result<AstNode, ParserError> {
-        // assignment_statement : variable ASSIGN expr
-
-        let left = self.variable()?;
-        let token = self.current_token.clone().unwrap();
-        self.eat(TokenKind::Assign)?;
-        let right = self.expr()?;
+    fn assignment_statement(&mut self, left: AstNode) -> Result<AstNode, ParserError> {
+        // assignment_statement : (variable | element) ASSIGN (assignment_statement | expr)
+        let token = self.eat(TokenKind::Assign)?;
+        // An assignment is worth the value assigned, so `a := b := 5` sets
+        // both; counting depth keeps a long chain from exhausting the stack
+        let right = self.nested(Self::expression_statement)?;
         Ok(AstNode::Assign(Box::new(left), Box::new(right), token))
     }
 
+    fn procedure_call_statement(&mut self, name: Token) -> Result<AstNode, ParserError> {
+        // procedure_call_statement : ID (LPAREN (expr (COMMA expr)*)? RPAREN)?
+        let args = if self.at(&[TokenKind::LParen]) {
+            self.arguments()?
+        } else {
+            vec![]
+        };
+        Ok(AstNode::ProcedureCall(name.value.parse::<String>(), args))
+    }
+
     fn variable(&mut self) -> Result<AstNode, ParserError> {
         // variable : ID
-        if let Some(token) = self.current_token.clone() {
-            if token.kind == TokenKind::Identifier {
-                self.eat(TokenKind::Identifier)?;
-                Ok(AstNode::Var(token))
+        match self.kind()? {
+            TokenKind::Identifier => Ok(AstNode::Var(self.advance()?)),
+            _ => Err(self.error("Invalid syntax")),
+        }
+    }
+
+    fn factor(&mut self) -> Result<AstNode, ParserError> {
+        // factor : PLUS factor | MINUS factor | if_expr | power
+        match self.kind()? {
+            TokenKind::Plus | TokenKind::Minus => self.signed(),
+            TokenKind::If => self.if_expr(),
+            _ => self.power(),
+        }
+    }
+
+    // A run of signs is read in a loop and folded into the one sign it
+    // amounts to, so `- - - 1` is `(- 1)` and `- - 1` is just `1`. However
+    // long the run, the tree stays shallow and nothing counts toward
+    // `max_depth`.
+    fn signed(&mut self) -> Result<AstNode, ParserError> {
+        let (mut minus, mut plus, mut negative) = (None, None, false);
+        while self.at(&[TokenKind::Plus, TokenKind::Minus]) {
+            let sign = self.advance()?;
+            if sign.kind == TokenKind::Plus {
+                plus = plus.or(Some(sign));
             } else {
-                Err(ParserError {
-                    message: "Invalid syntax".to_string(),
-                })
+                negative = !negative;
+                minus = minus.or(Some(sign));
             }
-        } else {
-            Err(ParserError {
-                message: "Unexpected end of input".to_string(),
-            })
         }
+        let node = self.factor()?;
+        Ok(match if negative { minus } else { plus } {
+            Some(sign) => AstNode::UnaryOp(Box::new(node), sign),
+            None => node,
+        })
     }
 
-    fn factor(&mut self) -> Result<AstNode, ParserError> {
-        if let Some(token) = self.current_token.clone() {
-            match token.kind {
-                TokenKind::Plus => {
-                    self.eat(TokenKind::Plus)?;
-                    Ok(AstNode::UnaryOp(Box::new(self.factor()?), token))
-                }
-                TokenKind::Minus => {
-                    self.eat(TokenKind::Minus)?;
-                    Ok(AstNode::UnaryOp(Box::new(self.factor()?), token))
-                }
-                TokenKind::Integer => {
-                    self.eat(TokenKind::Integer)?;
-                    Ok(AstNode::Num(AstType::Integer(token.value.parse::<i32>())))
-                }
-                TokenKind::Real => {
-                    self.eat(TokenKind::Real)?;
-                    Ok(AstNode::Num(AstType::Real(token.value.parse::<f64>())))
-                }
-                TokenKind::LParen => {
-                    self.eat(TokenKind::LParen)?;
-                    let result = self.expr()?;
-                    self.eat(TokenKind::RParen)?;
-                    Ok(result)
-                }
-                _ => self.variable(),
+    fn if_expr(&mut self) -> Result<AstNode, ParserError> {
+        // if_expr : IF expr THEN expr ELSE expr
+        self.eat(TokenKind::If)?;
+        let condition = self.expr()?;
+        self.eat(TokenKind::Then)?;
+        let then_expr = self.expr()?;
+        self.eat(TokenKind::Else)?;
+        let else_expr = self.expr()?;
+        // Only one branch runs, so mismatches are caught where the branch
+        // types are plain from the source
+        if let (Some(then_kind), Some(else_kind)) = (value_kind(&then_expr), value_kind(&else_expr))
+        {
+            if then_kind != else_kind {
+                return Err(self.error(&format!(
+                    "IF branches have different types: {} and {}",
+                    then_kind, else_kind
+                )));
             }
-        } else {
-            Err(ParserError {
-                message: "Unexpected end of input".to_string(),
-            })
         }
+        Ok(AstNode::IfExpr(
+            Box::new(condition),
+            Box::new(then_expr),
+            Box::new(else_expr),
+        ))
     }
 
-    fn term(&mut self) -> Result<AstNode, ParserError> {
-        // term : factor ((MUL | DIV) factor)*
-        let mut node = self.factor()?;
-        while let Some(token) = self.current_token.clone() {
-            match token.kind {
-                TokenKind::Multiply => {
-                    self.eat(TokenKind::Multiply)?;
-                    node = AstNode::BinaryOp(Box::new(node), Box::new(self.factor()?), token);
-                }
-                TokenKind::FloatDivide => {
-                    self.eat(TokenKind::FloatDivide)?;
-                    node = AstNode::BinaryOp(Box::new(node), Box::new(self.factor()?), token);
-                }
-                TokenKind::IntegerDivide => {
-                    self.eat(TokenKind::IntegerDivide)?;
-                    node = AstNode::BinaryOp(Box::new(node), Box::new(self.factor()?), token);
-                }
-                _ => break,
+    fn power(&mut self) -> Result<AstNode, ParserError> {
+        // power : atom (POWER factor)?
+        self.atom().and_then(|node| {
+            if self.at(&[TokenKind::Power]) {
+                return self.binary_op(node, |parser| parser.nested(Self::factor));
             }
+            Ok(node)
+        })
+    }
+
+    fn atom(&mut self) -> Result<AstNode, ParserError> {
+        // atom : INTEGER | REAL | STRING | CHAR_LITERAL | TRUE | FALSE | LPAREN expr RPAREN
+        // | function_call | variable
+        let token = self.current_token.as_ref().ok_or_else(unexpected_end)?;
+        match token.kind {
+            TokenKind::StringLiteral
+            | TokenKind::CharLiteral
+            | TokenKind::True
+            | TokenKind::False => self.literal(),
+            // The INTEGER and REAL type names share these kinds, so check
+            // that the token really is a literal
+            TokenKind::Integer
+                if matches!(token.value, TokenValue::Int(_) | TokenValue::BigInt(_)) =>
+            {
+                self.literal()
+            }
+            TokenKind::Real if matches!(token.value, TokenValue::Real(_)) => self.literal(),
+            TokenKind::LParen => self.parenthesized(),
+            _ => self.reference(),
         }
-        Ok(node)
     }
 
-    fn expr(&mut self) -> Result<AstNode, ParserError> {
-        let mut result = self.term()?;
-        while let Some(token) = self.current_token.clone() {
-            if token.kind == TokenKind::EOF {
-                break;
+    fn parenthesized(&mut self) -> Result<AstNode, ParserError> {
+        self.eat(TokenKind::LParen)?;
+        self.expr()
+            .and_then(|node| self.eat(TokenKind::RParen).map(|_| node))
+    }
+
+    // A variable, an array element or a function call
+    fn reference(&mut self) -> Result<AstNode, ParserError> {
+        let node = self.variable()?;
+        match &node {
+            AstNode::Var(name) if self.at(&[TokenKind::LParen]) => {
+                self.function_call(name.value.parse::<String>())
             }
-            if ![TokenKind::Plus, TokenKind::Minus].contains(&token.kind) {
-                break;
+            AstNode::Var(name) if self.at(&[TokenKind::LBracket]) => self.element(name.clone()),
+            _ => Ok(node),
+        }
+    }
+
+    // Kept apart from `atom` so that its temporaries don't add to the stack
+    // frame of every nested parenthesis.
+    fn literal(&mut self) -> Result<AstNode, ParserError> {
+        let token = self.advance()?;
+        let value = match token.kind {
+            TokenKind::StringLiteral => AstType::Str(token.value.parse::<String>()),
+            // The lexer only produces valid codes
+            TokenKind::CharLiteral => {
+                AstType::Char(char::from_u32(token.value.parse::<i32>() as u32).unwrap_or_default())
             }
-            match token.kind {
-                TokenKind::Plus => {
-                    self.eat(TokenKind::Plus)?;
-                    result = AstNode::BinaryOp(Box::new(result), Box::new(self.term()?), token);
-                }
-                TokenKind::Minus => {
-                    self.eat(TokenKind::Minus)?;
-                    result = AstNode::BinaryOp(Box::new(result), Box::new(self.term()?), token);
-                }
-                _ => break,
+            TokenKind::Integer => match token.value {
+                TokenValue::BigInt(value) => AstType::BigInt(value),
+                value => AstType::Integer(value.parse::<i32>()),
+            },
+            TokenKind::Real => AstType::Real(token.value.parse::<f64>()),
+            kind => AstType::Boolean(kind == TokenKind::True),
+        };
+        Ok(AstNode::Num(value))
+    }
+
+    fn element(&mut self, array: Token) -> Result<AstNode, ParserError> {
+        // element : ID LBRACKET expr RBRACKET
+        self.eat(TokenKind::LBracket)?;
+        let index = self.expr()?;
+        self.eat(TokenKind::RBracket)?;
+        Ok(AstNode::Index(array, Box::new(index)))
+    }
+
+    fn function_call(&mut self, name: String) -> Result<AstNode, ParserError> {
+        // function_call : ID LPAREN (expr (COMMA expr)*)? RPAREN
+        Ok(AstNode::FunctionCall(name, self.arguments()?))
+    }
+
+    fn arguments(&mut self) -> Result<Vec<AstNode>, ParserError> {
+        self.eat(TokenKind::LParen)?;
+        let mut args = vec![];
+        if !self.at(&[TokenKind::RParen]) {
+            args.push(self.argument()?);
+            while self.at(&[TokenKind::Comma]) {
+                self.eat(TokenKind::Comma)?;
+                args.push(self.argument()?);
             }
         }
-        Ok(result)
+        self.eat(TokenKind::RParen)?;
+        Ok(args)
     }
 
-    pub fn parse(&mut self) -> Result<AstNode, ParserError> {
-        let node = self.program()?;
-        if let Some(token) = self.current_token.clone() {
-            if token.kind != TokenKind::EOF {
-                return Err(ParserError {
-                    message: "Invalid syntax".to_string(),
-                });
+    fn argument(&mut self) -> Result<AstNode, ParserError> {
+        // argument : expr (COLON expr (COLON expr)?)?
+        self.expr().and_then(|value| {
+            if self.at(&[TokenKind::Colon]) {
+                return self.field(value);
             }
+            Ok(value)
+        })
+    }
+
+    // The `:width` and optional `:decimals` after an argument to write,
+    // writeln or format. Any call accepts them; the interpreter rejects
+    // them elsewhere.
+    fn field(&mut self, value: AstNode) -> Result<AstNode, ParserError> {
+        self.eat(TokenKind::Colon)?;
+        let width = self.expr()?;
+        let precision = if self.at(&[TokenKind::Colon]) {
+            self.eat(TokenKind::Colon)?;
+            self.expr()?
+        } else {
+            AstNode::NoOp
+        };
+        Ok(AstNode::Formatted(
+            Box::new(value),
+            Box::new(width),
+            Box::new(precision),
+        ))
+    }
+
+    fn term(&mut self) -> Result<AstNode, ParserError> {
+        // term : factor ((MUL | DIV | MOD | SHL | SHR) factor)*
+        self.factor()
+            .and_then(|node| self.chain(node, &MULTIPLYING_OPERATORS, Self::factor))
+    }
+
+    fn expr(&mut self) -> Result<AstNode, ParserError> {
+        self.nested(Self::disjunction)
+    }
+
+    // Runs a grammar rule one level deeper. Levels taken inside it end with
+    // it, even when the rule stops at an error.
+    fn nested(
+        &mut self,
+        rule: fn(&mut Self) -> Result<AstNode, ParserError>,
+    ) -> Result<AstNode, ParserError> {
+        let depth = self.depth;
+        self.deeper()?;
+        let result = rule(self);
+        self.depth = depth;
+        result
+    }
+
+    fn deeper(&mut self) -> Result<(), ParserError> {
+        if self.depth >= self.max_depth {
+            return Err(self.error("Expression too deeply nested"));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn disjunction(&mut self) -> Result<AstNode, ParserError> {
+        // expr : conjunction ((OR | XOR) conjunction)*
+        self.conjunction()
+            .and_then(|node| self.chain(node, &[TokenKind::Or, TokenKind::Xor], Self::conjunction))
+    }
+
+    fn conjunction(&mut self) -> Result<AstNode, ParserError> {
+        // conjunction : negation (AND negation)*
+        self.negation()
+            .and_then(|node| self.chain(node, &[TokenKind::And], Self::negation))
+    }
+
+    fn negation(&mut self) -> Result<AstNode, ParserError> {
+        // negation : NOT negation | comparison
+        if self.at(&[TokenKind::Not]) {
+            return self.unary_op(|parser| parser.nested(Self::negation));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<AstNode, ParserError> {
+        // comparison : simple_expr ((EQ | NE | LT | LE | GT | GE) simple_expr)?
+        self.simple_expr().and_then(|node| {
+            if self.at(&COMPARISON_OPERATORS) {
+                return self.binary_op(node, Self::simple_expr);
+            }
+            Ok(node)
+        })
+    }
+
+    fn simple_expr(&mut self) -> Result<AstNode, ParserError> {
+        // simple_expr : term ((PLUS | MINUS) term)*
+        self.term()
+            .and_then(|node| self.chain(node, &[TokenKind::Plus, TokenKind::Minus], Self::term))
+    }
+
+    // The rest of a left-associative run such as `1 + 2 - 3`, after its
+    // first operand. The run is read in a loop, so it doesn't count toward
+    // `max_depth`.
+    //
+    // Every nested parenthesis passes through each of the grammar rules, so
+    // they hand the work that follows an operand to `and_then` and to
+    // helpers like this one; that keeps its temporaries off the stack while
+    // the operand is being parsed.
+    fn chain(
+        &mut self,
+        mut node: AstNode,
+        operators: &[TokenKind],
+        operand: fn(&mut Self) -> Result<AstNode, ParserError>,
+    ) -> Result<AstNode, ParserError> {
+        while self.at(operators) {
+            node = self.binary_op(node, operand)?;
+        }
+        Ok(node)
+    }
+
+    // Takes the operator at the current token and parses its right operand.
+    // Building the node out here keeps the temporaries out of the frames of
+    // the grammar rules, which every nested parenthesis passes through.
+    fn binary_op(
+        &mut self,
+        left: AstNode,
+        operand: fn(&mut Self) -> Result<AstNode, ParserError>,
+    ) -> Result<AstNode, ParserError> {
+        let token = self.advance()?;
+        operand(self).map(|right| AstNode::BinaryOp(Box::new(left), Box::new(right), token))
+    }
+
+    fn unary_op(
+        &mut self,
+        operand: fn(&mut Self) -> Result<AstNode, ParserError>,
+    ) -> Result<AstNode, ParserError> {
+        let token = self.advance()?;
+        Ok(AstNode::UnaryOp(Box::new(operand(self)?), token))
+    }
+
+    fn expression_statement(&mut self) -> Result<AstNode, ParserError> {
+        // expression_statement : variable ASSIGN expr
+        // | expr
+        let node = self.expr()?;
+        if matches!(node, AstNode::Var(_) | AstNode::Index(..)) && self.at(&[TokenKind::Assign]) {
+            return self.assignment_statement(node);
         }
         Ok(node)
     }
+
+    pub fn parse_statements(&mut self) -> Result<Vec<AstNode>, ParserError> {
+        // statements : program
+        // | expression_statement (SEMI expression_statement)*
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        if self.at(&[TokenKind::Program, TokenKind::Begin]) {
+            return Ok(top_level_statements(self.parse()?));
+        }
+        let mut nodes = vec![self.expression_statement()?];
+        while self.at(&[TokenKind::Semi]) {
+            self.eat(TokenKind::Semi)?;
+            nodes.push(self.expression_statement()?);
+        }
+        self.check_missing_semicolon()?;
+        self.end_of_input()?;
+        Ok(nodes)
+    }
+
+    pub fn parse(&mut self) -> Result<AstNode, ParserError> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.program()
+    }
+}
+
+fn unexpected_end() -> ParserError {
+    ParserError {
+        message: "Unexpected end of input".to_string(),
+    }
+}
+
+// A token as it appears in error messages, like `Integer '5'`.
+fn describe(token: &Token) -> String {
+    match token.value.to_string().as_str() {
+        "" => token.kind.to_string(),
+        value => format!("{} '{}'", token.kind, value),
+    }
+}
+
+// The kind of value an expression gives, when that is clear without running
+// it. Integers and reals count as the same kind since they mix freely.
+fn value_kind(node: &AstNode) -> Option<&'static str> {
+    match node {
+        AstNode::Num(AstType::Integer(_) | AstType::BigInt(_) | AstType::Real(_)) => Some("number"),
+        AstNode::Num(AstType::Boolean(_)) => Some("BOOLEAN"),
+        // Characters join and compare with strings, so they count as text too
+        AstNode::Num(AstType::Str(_) | AstType::Char(_)) => Some("string"),
+        AstNode::UnaryOp(operand, token) if token.kind != TokenKind::Not => {
+            value_kind(operand).filter(|kind| *kind == "number")
+        }
+        AstNode::BinaryOp(_, _, token)
+            if [
+                TokenKind::Equal,
+                TokenKind::NotEqual,
+                TokenKind::LessThan,
+                TokenKind::LessEqual,
+                TokenKind::GreaterThan,
+                TokenKind::GreaterEqual,
+            ]
+            .contains(&token.kind) =>
+        {
+            Some("BOOLEAN")
+        }
+        AstNode::IfExpr(_, then_expr, _) => value_kind(then_expr),
+        _ => None,
+    }
+}
+
+fn top_level_statements(mut node: AstNode) -> Vec<AstNode> {
+    match &mut node {
+        AstNode::Program(_, block) => top_level_statements(mem::take(block)),
+        // Declarations are kept so procedures are still defined when run
+        AstNode::Block(declarations, compound_statement) => mem::take(declarations)
+            .into_iter()
+            .chain(top_level_statements(mem::take(compound_statement)))
+            .collect(),
+        AstNode::Compound(nodes) => mem::take(nodes)
+            .into_iter()
+            .filter(|node| !matches!(node, AstNode::NoOp))
+            .collect(),
+        _ => vec![node],
+    }
 }
 
 #[cfg(test)]
@@ -335,6 +949,45 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_parser_from_tokens() {
+        let tokens = vec![
+            Token::new(TokenKind::Integer, TokenValue::Int(3)),
+            Token::new(TokenKind::Plus, TokenValue::Str("+".to_string())),
+            Token::new(TokenKind::Integer, TokenValue::Int(4)),
+        ];
+        let mut parser = Parser::from_tokens(tokens);
+        assert_eq!(parser.parse().unwrap().to_string(), "(+ 3 4)");
+
+        let mut lexer = Lexer::new("a := (1 + 2) * 3".to_string());
+        let tokens = lexer.tokens().collect::<Result<Vec<_>, _>>().unwrap();
+        let mut parser = Parser::from_tokens(tokens);
+        let statements = parser.parse_statements().unwrap();
+        assert_eq!(statements[0].to_string(), "(:= a (* (+ 1 2) 3))");
+
+        let mut parser = Parser::from_tokens(vec![]);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn test_peek_token() {
+        let mut lexer = Lexer::new("a := 5".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        assert_eq!(parser.kind().unwrap(), TokenKind::Identifier);
+        assert_eq!(parser.peek_token().unwrap().kind, TokenKind::Assign);
+        assert_eq!(parser.peek_token().unwrap().kind, TokenKind::Assign);
+        parser.eat(TokenKind::Identifier).unwrap();
+        assert_eq!(parser.kind().unwrap(), TokenKind::Assign);
+        assert_eq!(parser.peek_token().unwrap().value, TokenValue::Int(5));
+
+        // Peeking doesn't change what gets parsed
+        let mut lexer = Lexer::new("a := 5".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        parser.peek_token().unwrap();
+        let statements = parser.parse_statements().unwrap();
+        assert_eq!(statements[0].to_string(), "(:= a 5)");
+    }
+
     #[test]
     fn test_parser_with_parentheses() {
         let mut lexer = Lexer::new("(3 + 5) * 2".to_string());
@@ -343,6 +996,83 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_parser_with_deeply_nested_parentheses() {
+        let depth = 150;
+        let source = format!("{}-1 + 2{} * 3", "(".repeat(depth), ")".repeat(depth));
+        let mut lexer = Lexer::new(source);
+        let mut parser = Parser::new(&mut lexer);
+        assert_eq!(parser.parse().unwrap().to_string(), "(* (+ (- 1) 2) 3)");
+    }
+
+    #[test]
+    fn test_chained_assignment() {
+        let mut lexer = Lexer::new("BEGIN a := b[1] := c := 5; d := 1 END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        assert_eq!(
+            parser.parse().unwrap().to_string(),
+            "(begin (:= a (:= (index b 1) (:= c 5))) (:= d 1))"
+        );
+
+        let mut lexer = Lexer::new("a := b + 1 := 5".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        assert!(parser.parse_statements().is_err());
+    }
+
+    #[test]
+    fn test_long_run_of_signs() {
+        let cases = [
+            (format!("{}2 * 3", "- +".repeat(1000)), "(* (+ 2) 3)"),
+            (format!("{}2 * 3", "- +".repeat(1001)), "(* (- 2) 3)"),
+            (format!("{}x", "-".repeat(100_000)), "x"),
+            (format!("{}x", "-".repeat(100_001)), "(- x)"),
+        ];
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input);
+            let mut parser = Parser::new(&mut lexer);
+            // Checks the tree by printing it, then drops it
+            assert_eq!(parser.parse().unwrap().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_parser_max_depth() {
+        let inputs = [
+            format!("{}1{}", "(".repeat(1000), ")".repeat(1000)),
+            format!("{}1{}", "-(".repeat(1000), ")".repeat(1000)),
+            format!("2{}", " ** 2".repeat(1000)),
+            format!("{}TRUE", "NOT ".repeat(100_000)),
+            format!("BEGIN {}a := 1 END.", "IF TRUE THEN ".repeat(1000)),
+            format!("PROGRAM P;{} BEGIN END.", " PROCEDURE Q;".repeat(1000)),
+            format!("{}a := 1{}.", "BEGIN ".repeat(1000), " END".repeat(1000)),
+        ];
+        for input in inputs {
+            let mut lexer = Lexer::new(input);
+            let mut parser = Parser::new(&mut lexer);
+            let message = parser.parse().unwrap_err().message;
+            assert!(
+                message.starts_with("Expression too deeply nested at line 1"),
+                "{}",
+                message
+            );
+        }
+
+        // The whole expression is one level and each parenthesis another
+        for (parens, ok) in [(9, true), (10, false)] {
+            let mut lexer = Lexer::new(format!("{}1{}", "(".repeat(parens), ")".repeat(parens)));
+            let mut parser = Parser::new(&mut lexer);
+            parser.max_depth = 10;
+            assert_eq!(parser.parse().is_ok(), ok);
+        }
+        // A flat run of operators is read in a loop and doesn't count
+        for operator in [" * 2", " + 1", " AND TRUE", " OR FALSE"] {
+            let mut lexer = Lexer::new(format!("1{}", operator.repeat(10_000)));
+            let mut parser = Parser::new(&mut lexer);
+            parser.max_depth = 10;
+            assert!(parser.parse().is_ok(), "{}", operator);
+        }
+    }
+
     #[test]
     fn test_parser_with_invalid_syntax() {
         let mut lexer = Lexer::new("3 +".to_string());
@@ -351,6 +1081,39 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parser_with_trailing_tokens() {
+        let cases = [
+            ("3 + 4 5", "Integer '5' at line 1, column 7"),
+            ("3 + 4 )", "RParen ')' at line 1, column 7"),
+            ("BEGIN END. x", "Identifier 'x' at line 1, column 12"),
+        ];
+        for (input, found) in cases {
+            let expected = format!("Invalid syntax: unexpected trailing token {}", found);
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            assert_eq!(parser.parse().unwrap_err().message, expected);
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            assert_eq!(parser.parse_statements().unwrap_err().message, expected);
+        }
+    }
+
+    #[test]
+    fn test_multiplicative_operators_share_a_level() {
+        let cases = [
+            ("20 DIV 3 MOD 2", "(MOD (DIV 20 3) 2)"),
+            ("2 * 3 DIV 2", "(DIV (* 2 3) 2)"),
+            ("8 / 4 * 2 MOD 3", "(MOD (* (/ 8 4) 2) 3)"),
+            ("1 + 6 MOD 4 \\ 2 - 1", "(- (+ 1 (\\ (MOD 6 4) 2)) 1)"),
+        ];
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            assert_eq!(parser.parse().unwrap().to_string(), expected, "{}", input);
+        }
+    }
+
     #[test]
     fn test_parser_with_unary_operator() {
         let mut lexer = Lexer::new("-3 + 5".to_string());
@@ -396,4 +1159,337 @@ END.  "
         let result = parser.parse();
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_parser_with_extra_end() {
+        let mut lexer = Lexer::new("BEGIN a := 5 END END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert_eq!(
+            result.unwrap_err().message,
+            "unexpected END at line 1, column 18"
+        );
+    }
+
+    #[test]
+    fn test_parser_with_missing_end() {
+        let mut lexer = Lexer::new("BEGIN BEGIN a := 5; END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert_eq!(
+            result.unwrap_err().message,
+            "unmatched BEGIN at line 1, column 1"
+        );
+        let mut lexer = Lexer::new("BEGIN a := 5;\n  BEGIN b := 1".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        assert_eq!(
+            parser.parse().unwrap_err().message,
+            "unmatched BEGIN at line 2, column 3"
+        );
+    }
+
+    #[test]
+    fn test_parser_reports_the_token_expected_inside_begin() {
+        let cases = [
+            (
+                "BEGIN 3+4; x := 5 END.",
+                "expected End but found Integer '3' at line 1, column 7",
+            ),
+            (
+                "BEGIN x := 1 ) END.",
+                "expected End but found RParen ')' at line 1, column 14",
+            ),
+            (
+                "BEGIN x := (5 END.",
+                "expected RParen but found End 'END' at line 1, column 15",
+            ),
+        ];
+        for (source, expected) in cases {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            assert_eq!(parser.parse().unwrap_err().message, expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_parser_error_reports_line_and_column() {
+        let mut lexer = Lexer::new("BEGIN\n  a := 5 + * 2;\nEND.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert_eq!(
+            result.unwrap_err().message,
+            "Invalid syntax at line 2, column 12"
+        );
+    }
+
+    #[test]
+    fn test_parser_with_if_statement() {
+        let mut lexer =
+            Lexer::new("BEGIN IF a THEN b := 1; IF a THEN b := 1 ELSE b := 2 END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse_statements().unwrap();
+        assert_eq!(result.len(), 2);
+        assert!(
+            matches!(&result[0], AstNode::If(_, _, else_branch) if matches!(**else_branch, AstNode::NoOp))
+        );
+        assert!(
+            matches!(&result[1], AstNode::If(_, _, else_branch) if matches!(**else_branch, AstNode::Assign(..)))
+        );
+    }
+
+    #[test]
+    fn test_parser_with_if_missing_then() {
+        let mut lexer = Lexer::new("BEGIN IF 1 a := 5 END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parser_with_if_expression() {
+        let mut lexer = Lexer::new("2 * IF a THEN 1 ELSE 2.5 + b".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse().unwrap();
+        assert_eq!(result.to_string(), "(* 2 (if a 1 (+ 2.5 b)))");
+
+        let mut lexer = Lexer::new("IF a THEN 1 ELSE 'one'".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let error = parser.parse().unwrap_err().to_string();
+        assert!(error.contains("IF branches have different types: number and string"));
+
+        let mut lexer = Lexer::new("IF a THEN 1".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn test_parser_with_while_statement() {
+        let mut lexer = Lexer::new("BEGIN WHILE i < 3 DO BEGIN i := i + 1 END END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse_statements().unwrap();
+        assert!(
+            matches!(&result[0], AstNode::While(_, body) if matches!(**body, AstNode::Compound(_)))
+        );
+    }
+
+    #[test]
+    fn test_parser_with_for_missing_direction() {
+        let mut lexer = Lexer::new("BEGIN FOR i := 1 5 DO x := i END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert_eq!(
+            result.unwrap_err().message,
+            "expected TO or DOWNTO at line 1, column 18"
+        );
+    }
+
+    #[test]
+    fn test_parser_with_comparison() {
+        let mut lexer = Lexer::new("1 + 2 < 3 * 4".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse().unwrap();
+        match &result {
+            AstNode::BinaryOp(_, _, token) => assert_eq!(token.kind, TokenKind::LessThan),
+            node => panic!("expected a comparison, found {:?}", node),
+        }
+    }
+
+    #[test]
+    fn test_parser_with_chained_comparison() {
+        let mut lexer = Lexer::new("1 < 2 < 3".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parser_with_unterminated_comment() {
+        let mut lexer = Lexer::new("3 + { oops".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert_eq!(
+            result.unwrap_err().message,
+            "unterminated comment at line 1, column 5"
+        );
+    }
+
+    #[test]
+    fn test_parser_with_unexpected_character() {
+        let mut lexer = Lexer::new("3 @ 4".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let message = parser.parse().unwrap_err().message;
+        assert!(message.contains('@'), "{}", message);
+    }
+
+    #[test]
+    fn test_parser_with_leading_unterminated_comment() {
+        let mut lexer = Lexer::new("{ oops".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse_statements();
+        assert_eq!(
+            result.unwrap_err().message,
+            "unterminated comment at line 1, column 1"
+        );
+    }
+
+    #[test]
+    fn test_parser_with_procedure() {
+        let mut lexer = Lexer::new(
+            "PROGRAM P; PROCEDURE Add(a, b : INTEGER; c : REAL); BEGIN x := a END; BEGIN Add(1, 2, 3); Reset END."
+                .to_string(),
+        );
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse_statements().unwrap();
+        assert_eq!(result.len(), 3);
+        assert!(
+            matches!(&result[0], AstNode::ProcedureDecl(name, params, _) if name == "Add" && params.len() == 3)
+        );
+        assert!(
+            matches!(&result[1], AstNode::ProcedureCall(name, args) if name == "Add" && args.len() == 3)
+        );
+        assert!(
+            matches!(&result[2], AstNode::ProcedureCall(name, args) if name == "Reset" && args.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_parser_error_names_expected_and_found_tokens() {
+        let mut lexer = Lexer::new("PROGRAM P BEGIN END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert_eq!(
+            result.unwrap_err().message,
+            "expected Semi but found Begin 'BEGIN' at line 1, column 11"
+        );
+
+        let mut lexer = Lexer::new("BEGIN a := 5 END".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert_eq!(
+            result.unwrap_err().message,
+            "expected Dot but found EOF at line 1, column 17"
+        );
+    }
+
+    #[test]
+    fn test_parser_with_function_call() {
+        let mut lexer = Lexer::new("lerp(0, 10, 0.5) + 1".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_parse_statements() {
+        let mut lexer = Lexer::new("x := 5; x + 1; x * 2".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse_statements().unwrap();
+        assert_eq!(result.len(), 3);
+        assert!(matches!(result[0], AstNode::Assign(..)));
+        assert!(matches!(result[1], AstNode::BinaryOp(..)));
+    }
+
+    #[test]
+    fn test_parse_statements_with_program() {
+        let mut lexer = Lexer::new("BEGIN a := 1; b := 2; END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse_statements().unwrap();
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn test_parser_with_missing_semicolon() {
+        let mut lexer = Lexer::new("BEGIN a := 1 b := 2 END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert_eq!(
+            result.unwrap_err().message,
+            "missing ';' between statements at line 1, column 14"
+        );
+    }
+
+    #[test]
+    fn test_parse_statements_with_missing_semicolon() {
+        let mut lexer = Lexer::new("a := 1 b := 2".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse_statements();
+        assert_eq!(
+            result.unwrap_err().message,
+            "missing ';' between statements at line 1, column 8"
+        );
+    }
+
+    #[test]
+    fn test_parser_with_empty_bodies() {
+        let cases = [
+            ("PROGRAM P; BEGIN END.", "(program P (block (begin ())))"),
+            ("BEGIN BEGIN END; END.", "(begin (begin ()) ())"),
+        ];
+        for (source, expected) in cases {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(&mut lexer);
+            assert_eq!(parser.parse().unwrap().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_parser_with_trailing_blank_lines() {
+        let mut lexer = Lexer::new("BEGIN END.\n\n  ".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_parser_error_after_multiline_comment() {
+        let source = "BEGIN\n{ spans\n  two lines }\n  x := ) \nEND.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        assert_eq!(
+            parser.parse().unwrap_err().message,
+            "Invalid syntax at line 4, column 8"
+        );
+    }
+
+    #[test]
+    fn test_parser_with_trailing_comment() {
+        let mut lexer =
+            Lexer::new("PROGRAM P; BEGIN END.\n{ fin du programme, café }\n".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_parser_with_integer_before_final_dot() {
+        let mut lexer = Lexer::new("BEGIN x := 5 END.".to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let result = parser.parse();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_array_declarations() {
+        let source = "PROGRAM P; VAR a, b : ARRAY[-1..3] OF REAL; BEGIN a[0] := b[1 + 1] END.";
+        let mut lexer = Lexer::new(source.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        assert_eq!(
+            parser.parse().unwrap().to_string(),
+            "(program P (block (array a -1 3 REAL) (array b -1 3 REAL) (begin (:= (index a 0) (index b (+ 1 1))))))"
+        );
+
+        let cases = [
+            ("5..1", "empty array range 5..1 at line 1, column 30"),
+            (
+                "1..n",
+                "array bounds must be integer literals at line 1, column 29",
+            ),
+        ];
+        for (range, expected) in cases {
+            let source = format!("PROGRAM P; VAR a : ARRAY[{}] OF INTEGER; BEGIN END.", range);
+            let mut lexer = Lexer::new(source);
+            let mut parser = Parser::new(&mut lexer);
+            assert_eq!(parser.parse().unwrap_err().message, expected);
+        }
+    }
 }
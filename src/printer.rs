@@ -0,0 +1,291 @@
+use crate::ast::{AstNode, AstType};
+use crate::token::TokenKind;
+
+// Binding strength of each grammar level, loosest first. Atoms (numbers,
+// variables and calls) bind tightest and never need parentheses.
+const OR: u8 = 1;
+const AND: u8 = 2;
+const NOT: u8 = 3;
+const COMPARISON: u8 = 4;
+const SUM: u8 = 5;
+const PRODUCT: u8 = 6;
+const UNARY: u8 = 7;
+const POWER: u8 = 8;
+const ATOM: u8 = 9;
+
+// Turns a tree back into source text, adding parentheses only where the
+// grammar needs them to keep the same shape when parsed again.
+pub fn to_source(node: &AstNode) -> String {
+    match node {
+        AstNode::Program(name, block) => format!("PROGRAM {}; {}.", name, to_source(block)),
+        AstNode::Block(declarations, compound_statement) => {
+            // Declarations keep their order; a CONST or VAR keyword starts
+            // each run of constants or variables
+            let mut text = String::new();
+            let mut section = "";
+            for declaration in declarations {
+                let keyword = match declaration {
+                    AstNode::ConstDecl(..) => "CONST",
+                    AstNode::VarDecl(..) | AstNode::ArrayDecl(..) => "VAR",
+                    _ => "",
+                };
+                if !keyword.is_empty() && keyword != section {
+                    text.push_str(keyword);
+                    text.push(' ');
+                }
+                section = keyword;
+                text.push_str(&to_source(declaration));
+                text.push_str(if keyword.is_empty() { " " } else { "; " });
+            }
+            text + &to_source(compound_statement)
+        }
+        AstNode::VarDecl(var, type_node) => {
+            format!("{} : {}", to_source(var), to_source(type_node))
+        }
+        AstNode::ArrayDecl(var, low, high, type_node) => format!(
+            "{} : ARRAY[{}..{}] OF {}",
+            to_source(var),
+            low,
+            high,
+            to_source(type_node)
+        ),
+        AstNode::ConstDecl(name, value) => format!("{} = {}", name, to_source(value)),
+        AstNode::Type(token) => token.lexeme.clone(),
+        AstNode::ProcedureDecl(name, params, block) => {
+            let params: Vec<String> = params.iter().map(to_source).collect();
+            if params.is_empty() {
+                format!("PROCEDURE {}; {};", name, to_source(block))
+            } else {
+                format!(
+                    "PROCEDURE {}({}); {};",
+                    name,
+                    params.join("; "),
+                    to_source(block)
+                )
+            }
+        }
+        AstNode::Compound(nodes) => {
+            let statements: Vec<String> = nodes.iter().map(to_source).collect();
+            // A trailing `;` leaves an empty statement, which prints as nothing
+            match statements.join("; ").trim_end() {
+                "" => "BEGIN END".to_string(),
+                statements => format!("BEGIN {} END", statements),
+            }
+        }
+        AstNode::NoOp => String::new(),
+        AstNode::Assign(left, right, _) => format!("{} := {}", to_source(left), to_source(right)),
+        AstNode::If(condition, then_branch, else_branch) => {
+            let mut then_text = to_source(then_branch);
+            if let AstNode::NoOp = **else_branch {
+                return format!("IF {} THEN {}", to_source(condition), then_text);
+            }
+            // Otherwise the ELSE would attach to the inner IF
+            if let AstNode::If(..) = **then_branch {
+                then_text = format!("BEGIN {} END", then_text);
+            }
+            format!(
+                "IF {} THEN {} ELSE {}",
+                to_source(condition),
+                then_text,
+                to_source(else_branch)
+            )
+        }
+        AstNode::While(condition, body) => {
+            format!("WHILE {} DO {}", to_source(condition), to_source(body))
+        }
+        AstNode::Repeat(body, condition) => {
+            let statements: Vec<String> = body.iter().map(to_source).collect();
+            format!(
+                "REPEAT {} UNTIL {}",
+                statements.join("; "),
+                to_source(condition)
+            )
+        }
+        AstNode::For(variable, start, end, direction, body) => format!(
+            "FOR {} := {} {} {} DO {}",
+            to_source(variable),
+            to_source(start),
+            if *direction == TokenKind::Downto {
+                "DOWNTO"
+            } else {
+                "TO"
+            },
+            to_source(end),
+            to_source(body)
+        ),
+        AstNode::Formatted(value, width, precision) => match **precision {
+            AstNode::NoOp => format!("{}:{}", to_source(value), to_source(width)),
+            _ => format!(
+                "{}:{}:{}",
+                to_source(value),
+                to_source(width),
+                to_source(precision)
+            ),
+        },
+        AstNode::ProcedureCall(name, args) if args.is_empty() => name.clone(),
+        AstNode::ProcedureCall(name, args) | AstNode::FunctionCall(name, args) => {
+            let args: Vec<String> = args.iter().map(to_source).collect();
+            format!("{}({})", name, args.join(", "))
+        }
+        node => expression(node, 0),
+    }
+}
+
+// Prints `node`, wrapping it in parentheses if it binds looser than `min`.
+fn expression(node: &AstNode, min: u8) -> String {
+    let (text, precedence) = match node {
+        AstNode::BinaryOp(left, right, token) => {
+            let precedence = binary_precedence(&token.kind);
+            // The operand levels follow the grammar: sums and products are
+            // left-associative, powers are right-associative and
+            // comparisons don't chain at all.
+            let (left_min, right_min) = match precedence {
+                OR => (OR, AND),
+                AND => (AND, NOT),
+                COMPARISON => (SUM, SUM),
+                SUM => (SUM, PRODUCT),
+                PRODUCT => (PRODUCT, UNARY),
+                _ => (ATOM, UNARY),
+            };
+            let text = format!(
+                "{} {} {}",
+                expression(left, left_min),
+                token.lexeme,
+                expression(right, right_min)
+            );
+            (text, precedence)
+        }
+        AstNode::UnaryOp(operand, token) if token.kind == TokenKind::Not => (
+            format!("{} {}", token.lexeme, expression(operand, NOT)),
+            NOT,
+        ),
+        AstNode::UnaryOp(operand, token) => (
+            format!("{}{}", token.lexeme, expression(operand, UNARY)),
+            UNARY,
+        ),
+        AstNode::Num(AstType::Integer(value)) if *value < 0 => (value.to_string(), UNARY),
+        AstNode::Num(AstType::Integer(value)) => (value.to_string(), ATOM),
+        AstNode::Num(AstType::BigInt(value)) => (value.to_string(), ATOM),
+        AstNode::Num(AstType::Real(value)) if *value < 0.0 => (format!("{:?}", value), UNARY),
+        AstNode::Num(AstType::Real(value)) => (format!("{:?}", value), ATOM),
+        AstNode::Num(AstType::Str(value)) => (format!("'{}'", value.replace('\'', "''")), ATOM),
+        AstNode::Num(AstType::Char(value)) => (format!("#{}", *value as u32), ATOM),
+        AstNode::Num(AstType::Boolean(true)) => ("TRUE".to_string(), ATOM),
+        AstNode::Num(AstType::Boolean(false)) => ("FALSE".to_string(), ATOM),
+        AstNode::Var(token) => (token.value.to_string(), ATOM),
+        AstNode::Index(token, index) => {
+            (format!("{}[{}]", token.value, expression(index, 0)), ATOM)
+        }
+        // The ELSE branch runs as far right as it can, so an IF inside a
+        // larger expression always needs parentheses
+        AstNode::IfExpr(condition, then_expr, else_expr) => (
+            format!(
+                "IF {} THEN {} ELSE {}",
+                expression(condition, 0),
+                expression(then_expr, 0),
+                expression(else_expr, 0)
+            ),
+            0,
+        ),
+        node => (to_source(node), ATOM),
+    };
+    if precedence < min {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+fn binary_precedence(kind: &TokenKind) -> u8 {
+    match kind {
+        TokenKind::Plus | TokenKind::Minus => SUM,
+        TokenKind::Multiply
+        | TokenKind::FloatDivide
+        | TokenKind::IntegerDivide
+        | TokenKind::Modulo => PRODUCT,
+        TokenKind::Power => POWER,
+        TokenKind::Or | TokenKind::Xor => OR,
+        TokenKind::Shl | TokenKind::Shr => PRODUCT,
+        TokenKind::And => AND,
+        _ => COMPARISON,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parse;
+    use crate::parser::Parser;
+
+    fn round_trip(source: &str) -> String {
+        to_source(&parse(source).unwrap())
+    }
+
+    #[test]
+    fn test_round_trip_keeps_needed_parentheses() {
+        let inputs = [
+            "3 + 5 * 2",
+            "(3 + 5) * 2",
+            "10 - (4 - 3)",
+            "10 - 4 - 3",
+            "2 ** 3 ** 2",
+            "(2 ** 3) ** 2",
+            "-(3 + 4) * 2",
+            "-2 ** 2",
+            "(-2) ** 2",
+            "2 * -3",
+            "7 DIV 2 MOD 3",
+            "1 + 2 < 3 * 4",
+            "NOT a < 1 AND (b OR c) OR TRUE",
+            "NOT (a AND b)",
+            "a AND (b AND c)",
+            "a XOR b OR 1 SHL 2 * 3",
+            "'it''s' + name",
+            "#65 + #66",
+            "lerp(0, 10, 0.5) + 1",
+            "(IF a < b THEN a ELSE b) * 2",
+            "IF a THEN 1 ELSE IF b THEN 2 ELSE 3",
+            "format(x + 1:8:2, 'a':width)",
+        ];
+        for input in inputs {
+            assert_eq!(round_trip(input), input);
+        }
+    }
+
+    #[test]
+    fn test_redundant_parentheses_are_dropped() {
+        assert_eq!(round_trip("((3)) + (5 * 2)"), "3 + 5 * 2");
+        assert_eq!(round_trip("(1 + 2) + 3"), "1 + 2 + 3");
+    }
+
+    #[test]
+    fn test_statements() {
+        assert_eq!(
+            round_trip("BEGIN a := 5; b := a * (2 + 1); END."),
+            "BEGIN a := 5; b := a * (2 + 1); END"
+        );
+        assert_eq!(
+            round_trip("BEGIN IF a < 1 THEN IF b THEN c := 1 ELSE c := 2 END."),
+            "BEGIN IF a < 1 THEN IF b THEN c := 1 ELSE c := 2 END"
+        );
+    }
+
+    #[test]
+    fn test_declaration_sections_keep_their_order() {
+        let source = "PROGRAM P; VAR a : INTEGER; CONST N = 2; VAR b, c : REAL; PROCEDURE Q; BEGIN END; VAR d : BOOLEAN; BEGIN END.";
+        assert_eq!(
+            round_trip(source),
+            "PROGRAM P; VAR a : INTEGER; CONST N = 2; VAR b : REAL; c : REAL; PROCEDURE Q; BEGIN END; VAR d : BOOLEAN; BEGIN END."
+        );
+    }
+
+    #[test]
+    fn test_output_parses_to_the_same_source() {
+        let source = "PROGRAM P; CONST LIMIT = 2 * 3; VAR x, y : INTEGER; a : ARRAY[-1..6] OF REAL; PROCEDURE Show(n : INTEGER); BEGIN y := n END; BEGIN FOR x := 3 DOWNTO 1 DO Show(x * 2); WHILE y > 0 DO y := y - 1; REPEAT x := x + 1; y := y + x UNTIL y > 10; a[x - 1] := a[0] * 2 END.";
+        let printed = round_trip(source);
+        let mut lexer = Lexer::new(printed.clone());
+        let mut parser = Parser::new(&mut lexer);
+        assert_eq!(to_source(&parser.parse().unwrap()), printed);
+    }
+}
@@ -1,5 +1,9 @@
 use std::fmt;
 
+use crate::interpreter::BigInt;
+
+#[allow(clippy::upper_case_acronyms)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenKind {
     Program,
@@ -9,6 +13,7 @@ pub enum TokenKind {
     Minus,
     Multiply,
     IntegerDivide,
+    Modulo,
     EOF,
     LParen,
     RParen,
@@ -22,9 +27,51 @@ pub enum TokenKind {
     Real,
     FloatDivide,
     Integer,
+    Power,
+    If,
+    Then,
+    Else,
+    Equal,
+    NotEqual,
+    LessThan,
+    LessEqual,
+    GreaterThan,
+    GreaterEqual,
+    While,
+    Do,
+    For,
+    To,
+    Downto,
+    Procedure,
+    Boolean,
+    True,
+    False,
+    And,
+    Or,
+    Not,
+    Const,
+    StringLiteral,
+    CharLiteral,
+    Char,
+    Array,
+    Of,
+    LBracket,
+    RBracket,
+    DotDot,
+    Repeat,
+    Until,
+    Xor,
+    Shl,
+    Shr,
 }
 
-pub const RESERVED_KEYWORDS: [(TokenKind, &str); 7] = [
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+pub const RESERVED_KEYWORDS: [(TokenKind, &str); 32] = [
     (TokenKind::Begin, "BEGIN"),
     (TokenKind::End, "END"),
     (TokenKind::Program, "PROGRAM"),
@@ -32,17 +79,49 @@ pub const RESERVED_KEYWORDS: [(TokenKind, &str); 7] = [
     (TokenKind::Real, "REAL"),
     (TokenKind::Integer, "INTEGER"),
     (TokenKind::IntegerDivide, "DIV"),
+    (TokenKind::Modulo, "MOD"),
+    (TokenKind::If, "IF"),
+    (TokenKind::Then, "THEN"),
+    (TokenKind::Else, "ELSE"),
+    (TokenKind::While, "WHILE"),
+    (TokenKind::Do, "DO"),
+    (TokenKind::For, "FOR"),
+    (TokenKind::To, "TO"),
+    (TokenKind::Downto, "DOWNTO"),
+    (TokenKind::Procedure, "PROCEDURE"),
+    (TokenKind::Boolean, "BOOLEAN"),
+    (TokenKind::Char, "CHAR"),
+    (TokenKind::Array, "ARRAY"),
+    (TokenKind::Of, "OF"),
+    (TokenKind::True, "TRUE"),
+    (TokenKind::False, "FALSE"),
+    (TokenKind::And, "AND"),
+    (TokenKind::Or, "OR"),
+    (TokenKind::Not, "NOT"),
+    (TokenKind::Const, "CONST"),
+    (TokenKind::Repeat, "REPEAT"),
+    (TokenKind::Until, "UNTIL"),
+    (TokenKind::Xor, "XOR"),
+    (TokenKind::Shl, "SHL"),
+    (TokenKind::Shr, "SHR"),
 ];
 
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     pub kind: TokenKind,
     pub value: TokenValue,
+    pub lexeme: String,
+    pub line: usize,
+    pub column: usize,
 }
 
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenValue {
     Int(i32),
+    // An integer literal too large for an Integer, lexed in bignum mode
+    BigInt(BigInt),
     Real(f64),
     Str(String),
 }
@@ -74,6 +153,17 @@ impl From<TokenValue> for String {
     }
 }
 
+impl fmt::Display for TokenValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenValue::Int(i) => write!(f, "{}", i),
+            TokenValue::BigInt(i) => write!(f, "{}", i),
+            TokenValue::Real(r) => write!(f, "{}", r),
+            TokenValue::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
 impl TokenValue {
     pub fn parse<T>(&self) -> T
     where
@@ -85,7 +175,14 @@ impl TokenValue {
 
 impl Token {
     pub fn new(kind: TokenKind, value: TokenValue) -> Token {
-        Token { kind, value }
+        let lexeme = value.to_string();
+        Token {
+            kind,
+            value,
+            lexeme,
+            line: 0,
+            column: 0,
+        }
     }
 }
 
@@ -107,4 +204,10 @@ mod tests {
             _ => panic!("Invalid token value"),
         }
     }
+
+    #[test]
+    fn test_token_lexeme_defaults_to_value() {
+        let token = Token::new(TokenKind::Integer, TokenValue::Int(42));
+        assert_eq!(token.lexeme, "42");
+    }
 }
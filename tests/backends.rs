@@ -0,0 +1,43 @@
+use calculator::ast::AstNode;
+use calculator::interpreter::Interpreter;
+use calculator::optimize::fold_constants;
+
+// The fixtures benches/backends.rs measures, along with the smaller ones
+// the other tests use.
+const FIXTURES: [&str; 3] = [
+    "tests/fixtures/loops.pas",
+    "tests/fixtures/program.pas",
+    "tests/fixtures/totals.pas",
+];
+
+// What a program printed and the final value of each global.
+fn run(tree: &AstNode) -> (String, Vec<String>) {
+    let mut interpreter = Interpreter::default();
+    interpreter.visit(tree).unwrap();
+    let mut globals: Vec<String> = interpreter
+        .global_scope
+        .iter()
+        .map(|(name, value)| format!("{} = {}", name, value))
+        .collect();
+    globals.sort();
+    (interpreter.take_output(), globals)
+}
+
+#[test]
+fn test_backends_agree_on_fixtures() {
+    for path in FIXTURES {
+        let source = std::fs::read_to_string(path).unwrap();
+        let tree = calculator::parse(&source).unwrap();
+        let folded = fold_constants(tree.clone());
+        assert_eq!(run(&tree), run(&folded), "{}", path);
+    }
+}
+
+#[test]
+fn test_loops_fixture_has_something_to_fold() {
+    let source = std::fs::read_to_string("tests/fixtures/loops.pas").unwrap();
+    let tree = calculator::parse(&source).unwrap();
+    let folded = fold_constants(tree.clone());
+    assert_ne!(tree.to_string(), folded.to_string());
+    assert_eq!(run(&folded).0, "total = 26687, x = 625.0, i = 1000\n");
+}
@@ -0,0 +1,115 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn calculator(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_calculator"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_expression_with_set_variables() {
+    let output = calculator(&["-e", "x * 2 + y", "--set", "x=21", "--set", "y=0.5"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42.5\n");
+}
+
+#[test]
+fn test_invalid_set_variable() {
+    let output = calculator(&["-e", "x * 2", "--set", "x"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Invalid --set value: x\n"
+    );
+}
+
+#[test]
+fn test_ast_dump() {
+    let output = calculator(&["--ast", "tests/fixtures/program.pas"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for kind in [
+        "Program", "Block", "VarDecl", "Compound", "Assign", "BinaryOp",
+    ] {
+        assert!(stdout.contains(kind), "missing {} in {}", kind, stdout);
+    }
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn test_ast_dump_as_json() {
+    let output = calculator(&["--ast", "--json", "tests/fixtures/program.pas"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"Program\""));
+    assert!(stdout.contains("\"Part10\""));
+}
+
+#[test]
+fn test_ast_dump_with_syntax_error() {
+    let output = calculator(&["--ast", "tests/fixtures/invalid.pas"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unexpected END at"));
+}
+
+#[test]
+fn test_run_program_file() {
+    let output = calculator(&["tests/fixtures/totals.pas"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "total = 10\n");
+}
+
+#[test]
+fn test_run_missing_file() {
+    let output = calculator(&["tests/fixtures/missing.pas"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("Could not read tests/fixtures/missing.pas"));
+}
+
+#[test]
+fn test_verbose_expression_statements() {
+    let output = calculator(&["-v", "-e", "3 + 4; x := 5"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "7\n");
+
+    let output = calculator(&["-v", "tests/fixtures/totals.pas"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "total = 10\n");
+}
+
+#[test]
+fn test_expression_error_fails() {
+    let output = calculator(&["-e", "1/0"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Division by zero"));
+
+    let output = calculator(&["-e", "write('x'); 1 +"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid syntax"));
+}
+
+#[test]
+fn test_piped_input_with_an_error_fails() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_calculator"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"2 + 3\n1 DIV 0\n4 * 5\n").unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("5\n") && stdout.contains("20\n"),
+        "{}",
+        stdout
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Division by zero"));
+}
@@ -0,0 +1,3 @@
+BEGIN
+   x := 5
+END END.
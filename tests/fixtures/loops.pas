@@ -0,0 +1,18 @@
+PROGRAM Loops;
+VAR
+   i, j, total : INTEGER;
+   x : REAL;
+BEGIN
+   total := 0;
+   x := 0.0;
+   FOR i := 1 TO 200 DO
+      FOR j := 1 TO 50 DO
+      BEGIN
+         total := (total + i * j + 60 * 60 DIV 24) MOD 100003;
+         x := x + 1.0 / (2 * 8)
+      END;
+   i := 0;
+   WHILE i < 1000 DO
+      i := i + 3 - 2;
+   writeln('total = ', total, ', x = ', x, ', i = ', i)
+END.
@@ -0,0 +1,7 @@
+PROGRAM Part10;
+VAR
+   x, y : INTEGER;
+BEGIN {Part10}
+   x := 5;
+   y := x * 2 + 1
+END.
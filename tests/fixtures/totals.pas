@@ -0,0 +1,9 @@
+PROGRAM Totals;
+VAR
+   i, total : INTEGER;
+BEGIN
+   total := 0;
+   FOR i := 1 TO 4 DO
+      total := total + i;
+   writeln('total = ', total)
+END.